@@ -71,6 +71,21 @@ class DBFRegistry:
 
             return [DbCredsSchema.model_validate(row) for row in rows]
 
+    def stored_passwords(self) -> list[DbCredsSchema]:
+        with self.session() as session:
+            rows = session.query(DbCredsModel).filter(DbCredsModel._password != "").all()
+            return [DbCredsSchema.model_validate(row) for row in rows]
+
+    def set_password(self, cred_id: int, password: str):
+        with self.session() as session:
+            row = session.get(DbCredsModel, cred_id)
+            if row:
+                row.password = password
+                session.commit()
+
+    def forget_password(self, cred_id: int):
+        self.set_password(cred_id, "")
+
     def delete_cred(self, name: str, host: str, port: str, user: str):
         with self.session() as session:
             row = (
@@ -218,7 +218,7 @@ class DatabaseFactory:
                 f.write("")
         return []
 
-    def save(self):
+    def save(self, keep_password: bool = True):
         pk = self.registry.save_cred(
             DbCredsSchema(
                 name=self.name,
@@ -226,7 +226,7 @@ class DatabaseFactory:
                 port=self.port,
                 dialect=self.dialect,
                 user=self.user,
-                password=self.password,
+                password=self.password if keep_password else "",
             )
         )
         self.id = pk
@@ -9,6 +9,12 @@ from typing import Any
 import uuid
 
 from core.helpers import requires
+from core.settings import (
+    APP_VERSION,
+    GEN_PROGRESS_INTERVAL,
+    LOG_PATH,
+    PROTOCOL_VERSION,
+)
 from core.populate.subprocess import generate_packets, run_sql_worker
 from core.utils.types import TableSpec
 
@@ -21,17 +27,23 @@ class Runner:
     def __init__(self):
         self.dbf = DatabaseFactory()
         self.populator = Populator()
+        self.framing = "line"
+        self._write_lock = threading.Lock()
+        self._after_write = []
 
     def listen(self):
-        for line in sys.stdin:
+        while (line := self._read()) is not None:
             line = line.strip()
             if not line:
                 continue
             elif line == "exit":
+                self._handle_clear_gen_packets()
+                self.dbf.disconnect()
                 res = json.dumps(Response(status="ok", payload="exiting...").to_dict())
-                print(res, flush=True)
+                self._write(res)
                 break
 
+            _id = None
             try:
                 req = json.loads(line)
                 req = Request(**req)
@@ -47,8 +59,57 @@ class Runner:
                 res_obj["id"] = _id
                 res = json.dumps(res_obj)
             finally:
+                self._write(res)
+                while self._after_write:
+                    self._after_write.pop(0)()
+
+    def _read(self) -> str | None:
+        """Reads one message, either a plain line or a `#<length>` frame."""
+        raw = sys.stdin.buffer.readline()
+        if not raw:
+            return None
+        header = raw.rstrip(b"\r\n")
+        if header.startswith(b"#") and header[1:].isdigit():
+            payload = sys.stdin.buffer.read(int(header[1:]))
+            sys.stdin.buffer.readline()
+            return payload.decode("utf-8", errors="replace")
+        return raw.decode("utf-8", errors="replace")
+
+    def _write(self, res: str):
+        with self._write_lock:
+            if self.framing == "length":
+                data = res.encode("utf-8")
+                sys.stdout.flush()
+                sys.stdout.buffer.write(b"#%d\n" % len(data) + data + b"\n")
+                sys.stdout.buffer.flush()
+            else:
                 print(res, flush=True)
 
+    def _emit(self, event: str, body: dict):
+        """Pushes an unsolicited event; it carries no id."""
+        self._write(json.dumps({"event": event, "body": body}))
+
+    def _watch_generation(self, job_id: str):
+        """Pushes progress for a generation job until it finishes or is replaced."""
+        last = None
+        while job_id == getattr(self, "_generation_id", None):
+            res = self._handle_poll_gen_status({"job_id": job_id})
+            if job_id != getattr(self, "_generation_id", None):
+                return
+            if res["status"] == "error":
+                self._emit("gen-done", {"job_id": job_id, "error": res["error"]})
+                return
+            if res["payload"]["status"] == "done":
+                self._emit(
+                    "gen-done", {"job_id": job_id, "data": res["payload"]["data"]}
+                )
+                return
+            progress = res["payload"].get("progress")
+            if progress != last:
+                self._emit("gen-progress", {"job_id": job_id, "progress": progress})
+                last = progress
+            time.sleep(GEN_PROGRESS_INTERVAL)
+
     def handle_command(self, command: Request) -> dict:
         try:
             handler = getattr(self, f"_handle_{command.kind}", None)
@@ -72,6 +133,24 @@ class Runner:
     def _handle_ping(self, _=None) -> dict:
         return self._ok("pong")
 
+    @requires()
+    def _handle_hello(self, body: dict | None = None) -> dict:
+        if "length" in ((body or {}).get("framing") or []):
+            self.framing = "length"
+        return self._ok(
+            {
+                "protocol": PROTOCOL_VERSION,
+                "version": APP_VERSION,
+                "framing": self.framing,
+                "logDir": LOG_PATH,
+                "kinds": sorted(
+                    name.removeprefix("_handle_")
+                    for name in dir(self)
+                    if name.startswith("_handle_")
+                ),
+            }
+        )
+
     @requires(connected=True)
     def _handle_get_db_info(self, _=None) -> dict:
         return self._ok(self.dbf.to_dict())
@@ -99,6 +178,9 @@ class Runner:
     @requires("host", "user", "port", "name", "password", "dialect")
     def _handle_set_db_connect(self, creds: dict) -> dict:
         self.dbf.disconnect()
+        # The shell keeps the password of a managed connection, in its vault
+        # or wherever its credential source points
+        managed = creds.get("managed", False)
         if saved := self.dbf.registry.exists(
             name=creds["name"],
             user=creds["user"],
@@ -107,6 +189,7 @@ class Runner:
             dialect=creds["dialect"],
         ):
             self.dbf.from_schema(saved)
+            self.dbf.password = creds["password"]
         else:
             self.dbf.from_dict(creds)
         try:
@@ -116,9 +199,15 @@ class Runner:
             return self._err(str(e))
 
         if not saved:
-            self.dbf.save()
+            self.dbf.save(keep_password=not managed)
+        elif not managed and saved.password != creds["password"]:
+            self.dbf.registry.set_password(saved.id, creds["password"])
 
-        return self._ok(self.dbf.to_dict())
+        payload = self.dbf.to_dict()
+        if managed and saved and saved.password:
+            # Forgotten once the shell has kept the new one
+            payload["storedPassword"] = True
+        return self._ok(payload)
 
     @requires("name", "host", "port", "user", "dialect")
     def _handle_set_db_reconnect(self, creds: dict):
@@ -130,16 +219,48 @@ class Runner:
             port=creds["port"],
             dialect=creds["dialect"],
         ):
+            stored = schema.password
+            if creds.get("password"):
+                schema.password = creds["password"]
             self.dbf.from_schema(schema)
             try:
                 self.dbf.test_connection()
-                return self._ok(self.dbf.to_dict())
             except Exception as e:
                 self.dbf.disconnect()
                 return self._err(str(e))
 
+            payload = self.dbf.to_dict()
+            if creds.get("managed") and stored:
+                # Handed over to the shell, which strips it from the reply and
+                # asks for it to be forgotten once it has kept it
+                payload["storedPassword"] = True
+                if not creds.get("password"):
+                    payload["password"] = stored
+            return self._ok(payload)
+
         return self._err("Unknown database.")
 
+    @requires()
+    def _handle_get_pref_passwords(self, _=None) -> dict:
+        creds = [
+            cred.model_dump(mode="json", exclude={"last_connected"})
+            for cred in self.dbf.registry.stored_passwords()
+        ]
+        return self._ok(creds)
+
+    @requires("name", "host", "port", "user", "dialect")
+    def _handle_set_pref_forget_password(self, creds: dict) -> dict:
+        for cred in self.dbf.registry.stored_passwords():
+            if (cred.name, cred.host, str(cred.port), cred.user, cred.dialect) == (
+                creds["name"],
+                creds["host"],
+                str(creds["port"]),
+                creds["user"],
+                creds["dialect"],
+            ):
+                self.dbf.registry.forget_password(cred.id)
+        return self._ok("Password forgotten.")
+
     @requires()
     def _handle_get_pref_connections(self, _=None) -> dict:
         creds = [cred.model_dump(mode="json") for cred in self.dbf.registry.list_creds()]
@@ -224,6 +345,12 @@ class Runner:
         )
         self._active_process.start()
 
+        # Only start pushing once the reply carrying the job id is out
+        watcher = threading.Thread(
+            target=self._watch_generation, args=(self._generation_id,), daemon=True
+        )
+        self._after_write.append(watcher.start)
+
         return self._ok(
             {
                 "status": "pending",
@@ -242,6 +369,8 @@ class Runner:
 
     @requires()
     def _handle_clear_gen_packets(self, _=None) -> dict:
+        # Stops the watcher without it reporting the job as crashed
+        self._generation_id = None
         if hasattr(self, "_active_process") and self._active_process.is_alive():
             self._active_process.terminate()
             self._active_process.join()
@@ -365,6 +494,10 @@ class Runner:
         self.dbf.rollback()
         return self._ok("Rollbacked all transactions successfully!")
 
+    @requires()
+    def _handle_get_uncommitted_db(self, _=None) -> dict:
+        return self._ok(getattr(self.dbf, "uncommitted", 0))
+
     @requires()
     def _handle_get_pref_rows(self, _: dict) -> dict:
         return self._ok(self.dbf.get_database_rows())
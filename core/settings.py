@@ -2,6 +2,11 @@ import os
 from platformdirs import user_data_dir
 
 APP_NAME = "DataSmith"
+APP_VERSION = "1.9.0"
+# Bump whenever request kinds or their bodies change incompatibly.
+PROTOCOL_VERSION = 1
+# Seconds between generation progress pushes.
+GEN_PROGRESS_INTERVAL = 0.1
 BASE_PATH = user_data_dir(appname=APP_NAME, appauthor=False)
 DB_PATH = os.path.join(BASE_PATH, "config.db")
 LOG_PATH = os.path.join(BASE_PATH, "logs")
@@ -6,6 +6,8 @@ from core.utils.response import Request
 import json
 import pytest
 from unittest.mock import patch, MagicMock
+from core.populate.factory import DatabaseFactory
+from core.settings import LOG_PATH
 from core.utils.response import Request
 
 TEST_CREDS = {
@@ -37,6 +39,52 @@ def test_handle_ping(runner: Runner):
     assert res["payload"] == "pong"
 
 
+def test_handle_hello(runner: Runner):
+    req = Request(kind="hello", body={})
+    res = runner.handle_command(req)
+    assert res["status"] == "ok"
+    assert isinstance(res["payload"]["protocol"], int)
+    assert "hello" in res["payload"]["kinds"]
+    assert "get_gen_packets" in res["payload"]["kinds"]
+    assert res["payload"]["logDir"] == LOG_PATH
+
+
+def test_handle_hello_negotiates_framing(runner: Runner):
+    res = runner.handle_command(Request(kind="hello", body={"framing": ["line"]}))
+    assert res["payload"]["framing"] == "line"
+
+    res = runner.handle_command(
+        Request(kind="hello", body={"framing": ["length", "line"]})
+    )
+    assert res["payload"]["framing"] == "length"
+    assert runner.framing == "length"
+
+
+def test_handle_get_uncommitted_db(runner: Runner):
+    req = Request(kind="get_uncommitted_db", body={})
+    res = runner.handle_command(req)
+    assert res["status"] == "ok"
+    assert res["payload"] == 0
+
+
+def test_watch_generation_reports_failure(runner: Runner):
+    runner._generation_id = "job"
+    with patch.object(runner, "_emit") as emit:
+        runner._watch_generation("job")
+    emit.assert_called_once()
+    event, body = emit.call_args.args
+    assert event == "gen-done"
+    assert body["job_id"] == "job"
+    assert body["error"]
+
+
+def test_watch_generation_stops_after_clear(runner: Runner):
+    runner._handle_clear_gen_packets()
+    with patch.object(runner, "_emit") as emit:
+        runner._watch_generation("job")
+    emit.assert_not_called()
+
+
 def test_handle_unknown_command(runner: Runner):
     req = Request(kind="unknown_cmd", body={})
     res = runner.handle_command(req)
@@ -151,6 +199,80 @@ def test_reconnect_deleted_db(runner: Runner):
     assert res["status"] == "error", res["error"]
 
 
+def test_managed_connect_keeps_password_out_of_registry(runner: Runner):
+    creds = {
+        "host": "vault.invalid",
+        "port": 3306,
+        "user": "root",
+        "name": "vaulted",
+        "dialect": "MYSQL",
+        "password": "secret",
+        "managed": True,
+    }
+    lookup = {k: creds[k] for k in ("name", "host", "port", "user", "dialect")}
+    try:
+        with patch.object(DatabaseFactory, "test_connection"):
+            res = runner.handle_command(Request(kind="set_db_connect", body=creds))
+            assert res["status"] == "ok", res["error"]
+            assert runner.dbf.registry.exists(**lookup).password == ""
+
+            res = runner.handle_command(Request(kind="set_db_reconnect", body=creds))
+            assert res["status"] == "ok", res["error"]
+            assert "password" not in res["payload"]
+    finally:
+        runner.dbf.disconnect()
+        runner.dbf.registry.delete_cred(
+            name="vaulted", host="vault.invalid", port="3306", user="root"
+        )
+
+
+def test_registry_password_is_kept_until_the_shell_forgets_it(runner: Runner):
+    creds = {
+        "host": "legacy.invalid",
+        "port": 3306,
+        "user": "root",
+        "name": "legacy",
+        "dialect": "MYSQL",
+        "password": "old",
+    }
+    lookup = {k: creds[k] for k in ("name", "host", "port", "user", "dialect")}
+    try:
+        with patch.object(DatabaseFactory, "test_connection"):
+            res = runner.handle_command(Request(kind="set_db_connect", body=creds))
+            assert res["status"] == "ok", res["error"]
+
+            # An unmanaged connect writes a changed password back
+            res = runner.handle_command(
+                Request(kind="set_db_connect", body={**creds, "password": "new"})
+            )
+            assert res["status"] == "ok", res["error"]
+            assert runner.dbf.registry.exists(**lookup).password == "new"
+
+            res = runner.handle_command(
+                Request(kind="set_db_reconnect", body={**lookup, "managed": True})
+            )
+            assert res["status"] == "ok", res["error"]
+            assert res["payload"]["password"] == "new"
+            assert res["payload"]["storedPassword"]
+            assert runner.dbf.registry.exists(**lookup).password == "new"
+
+            res = runner.handle_command(Request(kind="get_pref_passwords"))
+            assert {**lookup, "port": "3306", "password": "new"}.items() <= next(
+                cred for cred in res["payload"] if cred["host"] == "legacy.invalid"
+            ).items()
+
+            res = runner.handle_command(
+                Request(kind="set_pref_forget_password", body=lookup)
+            )
+            assert res["status"] == "ok", res["error"]
+            assert runner.dbf.registry.exists(**lookup).password == ""
+    finally:
+        runner.dbf.disconnect()
+        runner.dbf.registry.delete_cred(
+            name="legacy", host="legacy.invalid", port="3306", user="root"
+        )
+
+
 def test_empty_get_gen_packets(runner: Runner):
     req = Request(kind="set_db_reconnect", body=TEST_CREDS)
     res = runner.handle_command(req)
@@ -0,0 +1,246 @@
+//! The desktop app as a library: the bridge, its commands, the shell's log
+//! and the plugins the frontend relies on, ready to add to a Tauri builder.
+
+use std::{error::Error, sync::Arc};
+
+use tauri::{ipc::Invoke, App, AppHandle, Builder, Context, Manager, RunEvent, WindowEvent, Wry};
+
+use crate::bridge::{self, BridgeBuilder, Policy, Shared, Sidecar};
+use crate::{commands, logging, shell};
+
+/// Adds DataSmith to a Tauri app.
+///
+/// [`run`](DataSmithApp::run) is the whole desktop app. Apps embedding the
+/// bridge call [`apply`](DataSmithApp::apply) on their own builder, giving
+/// it their setup and commands rather than setting them on the builder, or
+/// call [`start`](DataSmithApp::start) from their own setup. Either way they
+/// hand their run events to [`on_run_event`].
+pub struct DataSmithApp {
+    sidecar: Option<Sidecar>,
+    plugins: bool,
+    logging: bool,
+    commands: bool,
+    host_setup: Option<HostSetup>,
+    host_handler: Option<HostHandler>,
+}
+
+type HostSetup = Box<dyn FnOnce(&mut App) -> Result<(), Box<dyn Error>> + Send>;
+type HostHandler = Box<dyn Fn(Invoke<Wry>) -> bool + Send + Sync>;
+
+impl Default for DataSmithApp {
+    fn default() -> Self {
+        DataSmithApp {
+            sidecar: None,
+            plugins: true,
+            logging: true,
+            commands: true,
+            host_setup: None,
+            host_handler: None,
+        }
+    }
+}
+
+impl DataSmithApp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `sidecar` instead of the populator bundled next to the app.
+    pub fn sidecar(mut self, sidecar: Sidecar) -> Self {
+        self.sidecar = Some(sidecar);
+        self
+    }
+
+    /// Whether to add the updater, opener and dialog plugins. Hosts that add
+    /// their own still need the dialog plugin for SQL confirmations.
+    pub fn plugins(mut self, plugins: bool) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Whether to install the shell's log as the process-wide logger.
+    pub fn logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    /// Whether to serve DataSmith's commands.
+    pub fn commands(mut self, commands: bool) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// The host's own setup, run once the bridge has started. A builder has
+    /// only one, so hosts pass theirs here instead.
+    pub fn host_setup(
+        mut self,
+        setup: impl FnOnce(&mut App) -> Result<(), Box<dyn Error>> + Send + 'static,
+    ) -> Self {
+        self.host_setup = Some(Box::new(setup));
+        self
+    }
+
+    /// The host's own invoke handler, given every command that isn't one of
+    /// DataSmith's. A builder has only one, so hosts pass theirs here
+    /// instead.
+    pub fn host_handler(
+        mut self,
+        handler: impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.host_handler = Some(Box::new(handler));
+        self
+    }
+
+    pub fn apply(mut self, mut builder: Builder<Wry>) -> Builder<Wry> {
+        if self.plugins {
+            builder = builder
+                .plugin(tauri_plugin_opener::init())
+                .plugin(tauri_plugin_dialog::init());
+            #[cfg(desktop)]
+            {
+                builder = builder.plugin(tauri_plugin_updater::Builder::new().build());
+            }
+        }
+        let host_handler = self.host_handler.take();
+        if self.commands {
+            let ours = invoke_handler();
+            builder = builder.invoke_handler(move |invoke| match &host_handler {
+                Some(host) if !COMMANDS.contains(&invoke.message.command()) => host(invoke),
+                _ => ours(invoke),
+            });
+        } else if let Some(host) = host_handler {
+            builder = builder.invoke_handler(host);
+        }
+        let host_setup = self.host_setup.take();
+        builder.setup(move |app| {
+            self.start(app.handle())?;
+            match host_setup {
+                Some(setup) => setup(app),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Starts the log and the bridge for `app` and puts the bridge in its
+    /// state.
+    pub fn start(&self, app: &AppHandle) -> Result<Shared, Box<dyn Error>> {
+        let data_dir = app.path().app_data_dir()?;
+        if self.logging {
+            if let Err(e) = logging::init(&data_dir.join("logs")) {
+                eprintln!("failed to start logging: {e}");
+            }
+        }
+        let sidecar = match &self.sidecar {
+            Some(sidecar) => sidecar.clone(),
+            None => bridge::populator_sidecar()?,
+        };
+        let run_dir = app
+            .path()
+            .runtime_dir()
+            .or_else(|_| app.path().app_local_data_dir())?
+            .join("datasmith");
+
+        let (bridge, _supervisor) = BridgeBuilder::new(sidecar, data_dir)
+            .run_dir(run_dir)
+            .policy(Policy::from_config(app.config().plugins.0.get("bridge")))
+            .events(Arc::new(app.clone()))
+            .spawn();
+        app.manage(bridge.clone());
+        Ok(bridge)
+    }
+
+    /// Builds the app from `context` and runs it until it exits.
+    pub fn run(self, context: Context<Wry>) {
+        self.apply(Builder::default())
+            .build(context)
+            .expect("build tauri")
+            .run(on_run_event);
+    }
+}
+
+/// Asks before closing the main window while work is pending and stops the
+/// populator on exit. Other windows close as usual.
+pub fn on_run_event(app: &AppHandle, event: RunEvent) {
+    match event {
+        RunEvent::WindowEvent {
+            label,
+            event: WindowEvent::CloseRequested { api, .. },
+            ..
+        } if label == bridge::MAIN_WINDOW => {
+            api.prevent_close();
+            bridge::confirm_close(app);
+        }
+        RunEvent::WindowEvent {
+            label,
+            event: WindowEvent::Destroyed,
+            ..
+        } => app.state::<Shared>().unfollow_logs(&label),
+        RunEvent::Exit => bridge::shutdown_on_exit(app),
+        _ => {}
+    }
+}
+
+macro_rules! serve_commands {
+    ($($module:ident::$command:ident),* $(,)?) => {
+        /// Names of the commands [`invoke_handler`] serves.
+        pub const COMMANDS: &[&str] = &[$(stringify!($command)),*];
+
+        /// Every command the frontend invokes.
+        pub fn invoke_handler() -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static {
+            tauri::generate_handler![$($module::$command),*]
+        }
+    };
+}
+
+serve_commands! {
+    shell::request,
+    shell::cancel_request,
+    shell::get_request_timeouts,
+    shell::set_request_timeouts,
+    shell::bridge_status,
+    shell::bridge_handshake,
+    shell::bridge_queue_stats,
+    shell::bridge_stderr_tail,
+    shell::bridge_journal_start,
+    shell::bridge_journal_stop,
+    shell::bridge_journal_path,
+    shell::bridge_socket_path,
+    shell::bridge_http_url,
+    shell::audit_query,
+    shell::vault_status,
+    shell::vault_unlock,
+    shell::vault_lock,
+    shell::credential_sources,
+    shell::set_credential_source,
+    shell::get_log_level,
+    shell::set_log_level,
+    shell::logs_query,
+    shell::logs_follow,
+    shell::logs_unfollow,
+    commands::ping,
+    commands::get_db_info,
+    commands::get_db_last_connected,
+    commands::set_db_connect,
+    commands::set_db_reconnect,
+    commands::set_db_disconnect,
+    commands::get_pref_connections,
+    commands::set_pref_delete,
+    commands::get_db_tables,
+    commands::get_db_table,
+    commands::get_gen_methods,
+    commands::get_gen_packets,
+    commands::get_gen_packet,
+    commands::poll_gen_status,
+    commands::clear_gen_packets,
+    commands::get_pref_spec,
+    commands::get_pref_rows,
+    commands::get_sql_banner,
+    commands::run_sql_query,
+    commands::get_logs_read,
+    commands::set_logs_clear,
+    commands::set_db_insert,
+    commands::set_db_export,
+    commands::set_db_commit,
+    commands::set_db_rollback,
+    commands::get_uncommitted_db,
+}
@@ -0,0 +1,438 @@
+//! Append-only record of every request that changes data or writes files.
+//!
+//! One JSON line per request, written once its outcome is known, including
+//! requests the policy rejected. A request the shell stopped waiting for is
+//! written as abandoned, and again as late when its reply turns up. The
+//! bridge follows connects, disconnects and generated packets so each line
+//! can say which database and table it touched.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{lock, Bridge, BridgeError};
+use crate::protocol::{camelize, DbCreds, DbDialect, TablePacket};
+
+const AUDITED_KINDS: &[&str] = &[
+    "set_db_insert",
+    "set_db_commit",
+    "set_db_rollback",
+    "set_db_export",
+    "run_sql_query",
+];
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+// How many abandoned requests still have their late replies audited.
+const AWAITING_CAPACITY: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection {
+    pub id: Option<i32>,
+    pub name: String,
+    pub user: String,
+    pub host: String,
+    pub port: String,
+    pub dialect: DbDialect,
+}
+
+impl Connection {
+    /// `user@host:port/name`, unique among saved connections.
+    pub fn identity(&self) -> String {
+        format!("{}@{}:{}/{}", self.user, self.host, self.port, self.name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Ok,
+    Error,
+    /// The shell stopped waiting, so whether the populator did the work is
+    /// unknown until a late entry follows.
+    Abandoned,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+    /// Shared by an abandoned entry and the late one that settles it.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    pub kind: String,
+    pub window: Option<String>,
+    pub connection: Option<Connection>,
+    pub table: Option<String>,
+    pub packet_id: Option<String>,
+    /// Uncommitted rows after an insert, or the rows a commit or rollback
+    /// settled.
+    pub pending_writes: Option<u32>,
+    pub sql: Option<String>,
+    pub path: Option<String>,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+    /// Written for a reply that came after the request was abandoned.
+    #[serde(default)]
+    pub late: bool,
+}
+
+/// Filters for [`Bridge::query_audit`]; dates are `YYYY-MM-DD` in UTC and
+/// inclusive.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// A connection name or its `user@host:port/name` identity.
+    pub connection: Option<String>,
+    /// Keeps only the most recent entries.
+    pub limit: Option<usize>,
+}
+
+#[derive(Default)]
+struct Tracked {
+    connection: Option<Connection>,
+    /// Table of each packet generated on the current connection.
+    packets: HashMap<String, String>,
+    pending_writes: u32,
+}
+
+/// What [`Audit::finish`] needs to remember about a request in flight.
+pub(super) enum Draft {
+    Entry(AuditEntry),
+    /// The reply to a connect leaves out the dialect; the request has it.
+    Connect(DbDialect),
+    Other(String),
+}
+
+/// A request's draft, finished as abandoned if the request is dropped before
+/// it has an outcome.
+pub(super) struct Unfinished<'a> {
+    audit: &'a Audit,
+    draft: Option<Draft>,
+}
+
+impl Unfinished<'_> {
+    pub(super) fn finish(mut self, res: &Result<Value, BridgeError>) {
+        if let Some(draft) = self.draft.take() {
+            self.audit.finish(draft, res);
+        }
+    }
+}
+
+impl Drop for Unfinished<'_> {
+    fn drop(&mut self) {
+        if let Some(Draft::Entry(entry)) = self.draft.take() {
+            let kind = entry.kind.clone();
+            self.audit
+                .finish(Draft::Entry(entry), &Err(BridgeError::Cancelled { kind }));
+        }
+    }
+}
+
+pub struct Audit {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    tracked: Mutex<Tracked>,
+    awaiting: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl Audit {
+    pub fn new(path: PathBuf) -> Self {
+        Audit {
+            path,
+            file: Mutex::new(None),
+            tracked: Mutex::new(Tracked::default()),
+            awaiting: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Captures what finishing request `id` of `kind` will need; audited
+    /// kinds start their entry here, before the populator can change
+    /// anything.
+    pub(super) fn begin(
+        &self,
+        kind: &str,
+        body: &Value,
+        window: Option<&str>,
+        id: &str,
+    ) -> Unfinished<'_> {
+        Unfinished {
+            audit: self,
+            draft: Some(self.draft(kind, body, window, id)),
+        }
+    }
+
+    fn draft(&self, kind: &str, body: &Value, window: Option<&str>, id: &str) -> Draft {
+        if matches!(kind, "set_db_connect" | "set_db_reconnect") {
+            let dialect = serde_json::from_value::<DbCreds>(camelize(body.clone()))
+                .map(|creds| creds.dialect)
+                .unwrap_or_default();
+            return Draft::Connect(dialect);
+        }
+        if !AUDITED_KINDS.contains(&kind) {
+            return Draft::Other(kind.to_string());
+        }
+        let body = camelize(body.clone());
+        let field = |name: &str| body.get(name).and_then(Value::as_str).map(String::from);
+        let tracked = lock(&self.tracked);
+        let packet_id = field("packetId");
+        Draft::Entry(AuditEntry {
+            at: now_ms(),
+            request_id: Some(id.to_string()),
+            kind: kind.to_string(),
+            window: window.map(String::from),
+            connection: tracked.connection.clone(),
+            table: packet_id
+                .as_ref()
+                .and_then(|id| tracked.packets.get(id).cloned()),
+            packet_id,
+            pending_writes: None,
+            sql: field("sql"),
+            path: field("path"),
+            outcome: Outcome::Ok,
+            error: None,
+            late: false,
+        })
+    }
+
+    /// Writes the entry of an audited request, and otherwise follows the
+    /// connection and generated packets so later entries can name them.
+    fn finish(&self, draft: Draft, res: &Result<Value, BridgeError>) {
+        let mut entry = match draft {
+            Draft::Entry(entry) => entry,
+            Draft::Connect(dialect) => return self.observe_connect(dialect, res),
+            Draft::Other(kind) => return self.observe(&kind, res),
+        };
+        match res {
+            Ok(payload) => {
+                let mut tracked = lock(&self.tracked);
+                match entry.kind.as_str() {
+                    "set_db_insert" => {
+                        let writes = camelize(payload.clone())
+                            .get("pendingWrites")
+                            .and_then(Value::as_u64)
+                            .map(|n| n as u32);
+                        entry.pending_writes = writes;
+                        tracked.pending_writes = writes.unwrap_or(tracked.pending_writes);
+                    }
+                    "set_db_commit" | "set_db_rollback" => {
+                        entry.pending_writes = Some(std::mem::take(&mut tracked.pending_writes));
+                    }
+                    _ => {}
+                }
+            }
+            Err(e @ (BridgeError::Timeout { .. } | BridgeError::Cancelled { .. })) => {
+                entry.outcome = Outcome::Abandoned;
+                entry.error = Some(e.to_string());
+                let mut awaiting = lock(&self.awaiting);
+                if awaiting.len() == AWAITING_CAPACITY {
+                    awaiting.pop_front();
+                }
+                awaiting.push_back(entry.clone());
+            }
+            Err(e) => {
+                entry.outcome = Outcome::Error;
+                entry.error = Some(e.to_string());
+            }
+        }
+        if let Err(e) = self.append(&entry) {
+            log::error!("failed to write audit entry for {}: {e}", entry.kind);
+        }
+    }
+
+    /// Writes what became of abandoned request `id` once its reply turns up.
+    pub(super) fn settle_late(&self, id: &str, res: &Result<Value, BridgeError>) {
+        let entry = {
+            let mut awaiting = lock(&self.awaiting);
+            awaiting
+                .iter()
+                .position(|entry| entry.request_id.as_deref() == Some(id))
+                .and_then(|at| awaiting.remove(at))
+        };
+        let Some(mut entry) = entry else {
+            return;
+        };
+        entry.at = now_ms();
+        entry.outcome = Outcome::Ok;
+        entry.error = None;
+        entry.late = true;
+        self.finish(Draft::Entry(entry), res);
+    }
+
+    fn observe_connect(&self, dialect: DbDialect, res: &Result<Value, BridgeError>) {
+        let Ok(payload) = res else {
+            return;
+        };
+        let connection = serde_json::from_value::<DbCreds>(camelize(payload.clone()))
+            .ok()
+            .map(|creds| Connection {
+                id: creds.id,
+                name: creds.name,
+                user: creds.user,
+                host: creds.host,
+                port: creds.port,
+                dialect,
+            });
+        *lock(&self.tracked) = Tracked {
+            connection,
+            ..Tracked::default()
+        };
+    }
+
+    fn observe(&self, kind: &str, res: &Result<Value, BridgeError>) {
+        let Ok(payload) = res else {
+            return;
+        };
+        let mut tracked = lock(&self.tracked);
+        match kind {
+            "set_db_disconnect" => *tracked = Tracked::default(),
+            "get_gen_packets" | "poll_gen_status" | "get_gen_packet" => {
+                let payload = camelize(payload.clone());
+                let packet = payload.get("data").unwrap_or(&payload);
+                if let Ok(packet) = serde_json::from_value::<TablePacket>(packet.clone()) {
+                    tracked.packets.insert(packet.id, packet.name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn note_packet(&self, packet: &TablePacket) {
+        lock(&self.tracked)
+            .packets
+            .insert(packet.id.clone(), packet.name.clone());
+    }
+
+    /// A new populator starts disconnected and without packets.
+    pub(super) fn reset(&self) {
+        *lock(&self.tracked) = Tracked::default();
+    }
+
+    fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+        let mut file = lock(&self.file);
+        if file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut options = OpenOptions::new();
+            options.create(true).append(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            *file = Some(options.open(&self.path)?);
+        }
+        let line = serde_json::to_string(entry)?;
+        let written = file.as_mut().map_or(Ok(()), |file| {
+            writeln!(file, "{line}").and_then(|_| file.flush())
+        });
+        if written.is_err() {
+            // Reopen next time rather than keep writing to a broken handle
+            file.take();
+        }
+        written
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, BridgeError> {
+        let rejected = |reason| BridgeError::Rejected {
+            kind: "audit_query".into(),
+            reason,
+        };
+        let since = query
+            .since
+            .as_deref()
+            .map(day_start)
+            .transpose()
+            .map_err(rejected)?;
+        let until = query
+            .until
+            .as_deref()
+            .map(|date| day_start(date).map(|start| start + MS_PER_DAY))
+            .transpose()
+            .map_err(rejected)?;
+
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(BridgeError::Io(e.to_string())),
+        };
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| BridgeError::Io(e.to_string()))?;
+            // A line torn by a crash mid-write shouldn't hide the rest
+            let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) else {
+                continue;
+            };
+            if since.is_some_and(|since| entry.at < since)
+                || until.is_some_and(|until| entry.at >= until)
+            {
+                continue;
+            }
+            if let Some(wanted) = &query.connection {
+                let matches = entry
+                    .connection
+                    .as_ref()
+                    .is_some_and(|c| c.name == *wanted || c.identity() == *wanted);
+                if !matches {
+                    continue;
+                }
+            }
+            entries.push(entry);
+        }
+        if let Some(limit) = query.limit {
+            entries.drain(..entries.len().saturating_sub(limit));
+        }
+        Ok(entries)
+    }
+}
+
+/// Milliseconds at the start of a `YYYY-MM-DD` day in UTC.
+fn day_start(date: &str) -> Result<u64, String> {
+    let invalid = || format!("expected a YYYY-MM-DD date, got {date}");
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>());
+    let (Some(Ok(y)), Some(Ok(m)), Some(Ok(d))) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return Err(invalid());
+    }
+    // Days from civil, after Howard Hinnant's algorithm
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days)
+        .map(|days| days * MS_PER_DAY)
+        .map_err(|_| invalid())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+impl Bridge {
+    pub fn query_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, BridgeError> {
+        self.audit.query(query)
+    }
+
+    /// The database the populator is connected to, as far as the bridge has
+    /// seen.
+    pub fn connection(&self) -> Option<Connection> {
+        lock(&self.audit.tracked).connection.clone()
+    }
+}
@@ -0,0 +1,99 @@
+use std::{path::PathBuf, sync::Arc, thread::JoinHandle};
+
+use super::{
+    spawn_supervisor, Bridge, EventSink, Fanout, Policy, Shared, Sidecar, HTTP_ENV, JOURNAL_ENV,
+};
+
+/// Sets a bridge up the way the app does: vault, credential sources, policy
+/// and the servers the environment asks for, then starts supervising the
+/// sidecar.
+pub struct BridgeBuilder {
+    sidecar: Sidecar,
+    data_dir: PathBuf,
+    run_dir: Option<PathBuf>,
+    policy: Policy,
+    events: Vec<Arc<dyn EventSink>>,
+}
+
+impl BridgeBuilder {
+    /// Keeps logs, the vault and credential sources under `data_dir`.
+    pub fn new(sidecar: Sidecar, data_dir: PathBuf) -> Self {
+        BridgeBuilder {
+            sidecar,
+            data_dir,
+            run_dir: None,
+            policy: Policy::default(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Where the socket and HTTP API advertise themselves. Without one they
+    /// stay off whatever the environment says.
+    pub fn run_dir(mut self, dir: PathBuf) -> Self {
+        self.run_dir = Some(dir);
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Adds a sink for the bridge's events; the app's windows, usually.
+    pub fn events(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.events.push(sink);
+        self
+    }
+
+    pub fn spawn(self) -> (Shared, JoinHandle<()>) {
+        let BridgeBuilder {
+            sidecar,
+            data_dir,
+            run_dir,
+            policy,
+            mut events,
+        } = self;
+
+        let bridge = Bridge::new(data_dir.join("logs"));
+        bridge.use_vault(data_dir.join("vault.json"));
+        bridge.use_credential_sources(data_dir.join("credential-sources.json"));
+        bridge.set_policy(policy);
+        if std::env::var_os(JOURNAL_ENV).is_some() {
+            match bridge.start_journal() {
+                Ok(path) => log::info!("journaling bridge traffic to {}", path.display()),
+                Err(e) => log::warn!("failed to start bridge journal: {e}"),
+            }
+        }
+        if let Some(run_dir) = run_dir {
+            #[cfg(unix)]
+            if std::env::var_os(super::SOCKET_ENV).is_some() {
+                match bridge.serve_socket(run_dir.join("bridge.sock")) {
+                    Ok(()) => log::info!("listening on {:?}", bridge.socket_path()),
+                    Err(e) => log::warn!("failed to open bridge socket: {e}"),
+                }
+            }
+            if let Some(port) = std::env::var_os(HTTP_ENV) {
+                let served = port
+                    .to_string_lossy()
+                    .parse::<u16>()
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+                    .and_then(|port| bridge.serve_http(port, run_dir.join("http.json")));
+                match served {
+                    Ok(stream) => {
+                        log::info!(
+                            "serving http api on {:?}",
+                            bridge.http_info().map(|i| i.url)
+                        );
+                        events.push(stream);
+                    }
+                    Err(e) => log::warn!("failed to start http api: {e}"),
+                }
+            }
+        }
+
+        let events: Arc<dyn EventSink> = Arc::new(Fanout(events));
+        bridge.stream_logs(events.clone());
+        let supervisor = spawn_supervisor(events, sidecar, bridge.clone());
+        (bridge, supervisor)
+    }
+}
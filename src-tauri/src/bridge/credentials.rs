@@ -0,0 +1,325 @@
+//! Passwords read at connect time from where the user keeps them.
+//!
+//! A connection can have a [`CredentialSource`] instead of a password. The
+//! bridge reads the password from it before a connect goes out and again on
+//! every reconnect, so rotating credentials are never stored by DataSmith.
+//!
+//! A source decides what gets read from this machine and sent to a host the
+//! request names, so a request body can't carry one: sources are only saved
+//! through [`confirm_credential_source`], once the user has approved them in
+//! a native dialog.
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    io::Read,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::oneshot;
+
+use super::{lock, vault::identity, Bridge, BridgeError, Shared};
+use crate::protocol::{camelize, CredentialSource, DbCreds};
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialSource::Env { name } => write!(f, "environment variable {name}"),
+            CredentialSource::DotEnv { path, key } => write!(f, "{key} in {path}"),
+            CredentialSource::Command { command } => write!(f, "command `{command}`"),
+        }
+    }
+}
+
+/// What [`Sources::settle`] does once a request has worked.
+pub(super) enum SourceTicket {
+    Remember(String, CredentialSource),
+    Forget(String),
+}
+
+/// Sources by connection identity, kept in a plain JSON file since they
+/// hold no secrets.
+#[derive(Default)]
+pub struct Sources {
+    path: Mutex<Option<PathBuf>>,
+    saved: Mutex<BTreeMap<String, CredentialSource>>,
+}
+
+impl Sources {
+    /// Keeps the source of a connect that worked, or drops it once the
+    /// connection is deleted or given a password of its own.
+    pub(super) fn settle(&self, ticket: Option<SourceTicket>) {
+        let mut saved = lock(&self.saved);
+        let changed = match ticket {
+            Some(SourceTicket::Remember(identity, source)) => {
+                saved.insert(identity, source.clone()) != Some(source)
+            }
+            Some(SourceTicket::Forget(identity)) => saved.remove(&identity).is_some(),
+            None => false,
+        };
+        if !changed {
+            return;
+        }
+        let Some(path) = lock(&self.path).clone() else {
+            return;
+        };
+        let written = serde_json::to_vec_pretty(&*saved)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, json).map_err(|e| e.to_string())
+            });
+        if let Err(e) = written {
+            log::error!("failed to save credential sources: {e}");
+        }
+    }
+}
+
+fn resolve(source: &CredentialSource) -> Result<String, String> {
+    match source {
+        CredentialSource::Env { name } => env::var(name).map_err(|e| match e {
+            env::VarError::NotPresent => "it is not set".into(),
+            env::VarError::NotUnicode(_) => "it is not valid UTF-8".into(),
+        }),
+        CredentialSource::DotEnv { path, key } => read_dotenv(path, key),
+        CredentialSource::Command { command } => run(command),
+    }
+}
+
+fn read_dotenv(path: &str, key: &str) -> Result<String, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("failed to read it: {e}"))?;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim() != key {
+            continue;
+        }
+        let value = value.trim();
+        for quote in ['"', '\''] {
+            if let Some(inner) = value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+            {
+                return Ok(inner.to_string());
+            }
+        }
+        // Unquoted values end at a comment
+        let value = value.split(" #").next().unwrap_or_default();
+        return Ok(value.trim_end().to_string());
+    }
+    Err("no such key".into())
+}
+
+fn run(command: &str) -> Result<String, String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        shell.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = shell
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to start it: {e}"))?;
+
+    // Drained as it runs, so a chatty command can't stall on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(format!(
+                    "it did not finish within {}s",
+                    COMMAND_TIMEOUT.as_secs()
+                ));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let reason = stderr.lines().map(str::trim).find(|line| !line.is_empty());
+        return Err(match reason {
+            Some(reason) => format!("it failed with {status}: {reason}"),
+            None => format!("it failed with {status}"),
+        });
+    }
+    let stdout = String::from_utf8(stdout).map_err(|_| "it printed invalid UTF-8")?;
+    match stdout.lines().next() {
+        Some(line) if !line.is_empty() => Ok(line.trim_end_matches('\r').to_string()),
+        _ => Err("it printed nothing".into()),
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut out = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut out).ok();
+        }
+        out
+    })
+}
+
+impl Bridge {
+    /// Remembers credential sources in the file at `path` from now on.
+    pub fn use_credential_sources(&self, path: PathBuf) {
+        let saved = match fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json).unwrap_or_else(|e| {
+                log::warn!("ignoring invalid credential sources: {e}");
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        *lock(&self.sources.saved) = saved;
+        *lock(&self.sources.path) = Some(path);
+    }
+
+    /// Reads the password of the connection `creds` names from `source` from
+    /// now on.
+    pub fn save_credential_source(&self, creds: &DbCreds, source: CredentialSource) {
+        self.sources
+            .settle(Some(SourceTicket::Remember(identity(creds), source)));
+    }
+
+    /// The source of each connection that has one, by `user@host:port/name`.
+    pub fn credential_sources(&self) -> BTreeMap<String, CredentialSource> {
+        lock(&self.sources.saved).clone()
+    }
+
+    /// Reads the password of a connect from the source it names, or of a
+    /// reconnect from the source its connection was saved with.
+    pub(super) async fn resolve_password(
+        &self,
+        kind: &str,
+        body: &mut Value,
+    ) -> Result<Option<SourceTicket>, BridgeError> {
+        if !matches!(
+            kind,
+            "set_db_connect" | "set_db_reconnect" | "set_pref_delete"
+        ) {
+            return Ok(None);
+        }
+        let Ok(creds) = serde_json::from_value::<DbCreds>(camelize(body.clone())) else {
+            return Ok(None);
+        };
+        let identity = identity(&creds);
+        if kind == "set_pref_delete" {
+            return Ok(Some(SourceTicket::Forget(identity)));
+        }
+
+        let source = match (creds.password_source, creds.password) {
+            (Some(_), _) => {
+                return Err(BridgeError::Rejected {
+                    kind: kind.into(),
+                    reason: "password sources can only be set up from the app".into(),
+                });
+            }
+            (None, Some(_)) if kind == "set_db_connect" => {
+                return Ok(Some(SourceTicket::Forget(identity)));
+            }
+            (None, Some(_)) => return Ok(None),
+            (None, None) => match lock(&self.sources.saved).get(&identity) {
+                Some(source) => source.clone(),
+                None => return Ok(None),
+            },
+        };
+        let reading = source.clone();
+        let password = tauri::async_runtime::spawn_blocking(move || resolve(&reading))
+            .await
+            .map_err(|e| BridgeError::Io(e.to_string()))?
+            .map_err(|e| BridgeError::Rejected {
+                kind: kind.into(),
+                reason: format!("could not read the password from {source}: {e}"),
+            })?;
+
+        if let Value::Object(body) = body {
+            body.remove("passwordSource");
+            body.remove("password_source");
+            body.insert("password".into(), Value::String(password));
+            body.insert("managed".into(), Value::Bool(true));
+        }
+        Ok(Some(SourceTicket::Remember(identity, source)))
+    }
+}
+
+/// Asks in a native dialog over `window` whether DataSmith may read the
+/// password of the connection `creds` names from `source`, and saves it as
+/// that connection's source if the user agrees.
+pub async fn confirm_credential_source(
+    app: &AppHandle,
+    window: &str,
+    creds: &DbCreds,
+    source: CredentialSource,
+) -> Result<(), BridgeError> {
+    let identity = identity(creds);
+    let message = match &source {
+        CredentialSource::Command { command } => format!(
+            "DataSmith will run this command on every connect to {identity} and use the first line it prints as the password:\n\n{command}"
+        ),
+        source => format!(
+            "DataSmith will read the {source} on every connect to {identity} and send it to that database as the password."
+        ),
+    };
+    let (tx, rx) = oneshot::channel();
+    let mut dialog = app
+        .dialog()
+        .message(message)
+        .title("Use a password source?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Allow".into(),
+            "Cancel".into(),
+        ));
+    if let Some(parent) = app.get_webview_window(window) {
+        dialog = dialog.parent(&parent);
+    }
+    dialog.show(move |allowed| {
+        tx.send(allowed).ok();
+    });
+
+    match rx.await {
+        Ok(true) => {
+            app.state::<Shared>().save_credential_source(creds, source);
+            Ok(())
+        }
+        _ => Err(BridgeError::Cancelled {
+            kind: "set_credential_source".into(),
+        }),
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{pending, Bridge, EventSink};
+
+pub const DIAGNOSTIC_EVENT: &str = "bridge-diagnostic";
+
+// Keeps a runaway line from flooding the event channel.
+const MAX_RAW_LEN: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    NonJson,
+    MissingId,
+    UnknownId,
+    /// An `event` push we don't know or whose body doesn't parse.
+    InvalidEvent,
+}
+
+/// A line from the populator's stdout that could not be routed to a request.
+#[derive(Clone, Debug, Serialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub id: Option<String>,
+    pub raw: String,
+    /// The window that gave up on the request this line answers, if known.
+    #[serde(skip)]
+    pub window: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(kind: DiagnosticKind, id: Option<String>, mut raw: String) -> Self {
+        if raw.len() > MAX_RAW_LEN {
+            let mut end = MAX_RAW_LEN;
+            while !raw.is_char_boundary(end) {
+                end -= 1;
+            }
+            raw.truncate(end);
+            raw.push('…');
+        }
+        Diagnostic {
+            kind,
+            id,
+            raw,
+            window: None,
+        }
+    }
+
+    pub fn report(self, events: &dyn EventSink) {
+        log::warn!(
+            "unroutable populator output ({:?}): {}",
+            self.kind,
+            self.raw
+        );
+        match (self.window.clone(), self.kind) {
+            (Some(window), _) => events.emit_to(Some(&window), DIAGNOSTIC_EVENT, self),
+            // A reply whose window is unknown may hold another caller's results
+            (None, DiagnosticKind::UnknownId) => {}
+            (None, _) => events.emit(DIAGNOSTIC_EVENT, self),
+        }
+    }
+}
+
+impl Bridge {
+    /// Completes the request a stdout line answers or handles the event it
+    /// pushes, or explains why it can't.
+    pub(super) fn route(&self, events: &dyn EventSink, line: String) -> Result<(), Diagnostic> {
+        let mut parsed: Value = match serde_json::from_str(&line) {
+            Ok(parsed) => parsed,
+            Err(_) => return Err(Diagnostic::new(DiagnosticKind::NonJson, None, line)),
+        };
+        if let Some(event) = parsed
+            .get("event")
+            .and_then(Value::as_str)
+            .map(String::from)
+        {
+            let body = parsed.get_mut("body").map(Value::take).unwrap_or_default();
+            if !self.on_event(events, &event, body) {
+                return Err(Diagnostic::new(DiagnosticKind::InvalidEvent, None, line));
+            }
+            return Ok(());
+        }
+        let Some(id) = parsed.get("id").and_then(Value::as_str).map(String::from) else {
+            return Err(Diagnostic::new(DiagnosticKind::MissingId, None, line));
+        };
+
+        match self.resolve(&id, parsed) {
+            None => Ok(()),
+            // A late reply holds results only its own window may see
+            Some(late) => {
+                self.audit.settle_late(&id, &pending::into_result(late));
+                let window = self.abandoned_window(&id);
+                let diagnostic = Diagnostic::new(DiagnosticKind::UnknownId, Some(id), line);
+                Err(Diagnostic {
+                    window,
+                    ..diagnostic
+                })
+            }
+        }
+    }
+}
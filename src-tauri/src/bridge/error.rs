@@ -0,0 +1,81 @@
+use std::{fmt, time::Duration};
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// Why a bridge request did not produce a payload.
+#[derive(Debug)]
+pub enum BridgeError {
+    /// No populator is attached, e.g. while it is restarting.
+    Missing,
+    /// The write queue is full and the caller could not wait for room.
+    QueueFull,
+    Io(String),
+    Timeout {
+        kind: String,
+        after: Duration,
+    },
+    Cancelled {
+        kind: String,
+    },
+    /// The populator exited before it answered.
+    Exited {
+        kind: String,
+    },
+    /// The populator answered with `status: "error"`.
+    Sidecar(String),
+    /// A typed command could not encode its body or decode the reply.
+    Protocol(String),
+    /// The window may not send this kind, or its body doesn't match the
+    /// kind's schema; the populator never saw it.
+    Rejected {
+        kind: String,
+        reason: String,
+    },
+}
+
+impl BridgeError {
+    fn code(&self) -> &'static str {
+        match self {
+            BridgeError::Missing => "missing",
+            BridgeError::QueueFull => "queue-full",
+            BridgeError::Io(_) => "io",
+            BridgeError::Timeout { .. } => "timeout",
+            BridgeError::Cancelled { .. } => "cancelled",
+            BridgeError::Exited { .. } => "exited",
+            BridgeError::Sidecar(_) => "sidecar",
+            BridgeError::Protocol(_) => "protocol",
+            BridgeError::Rejected { .. } => "rejected",
+        }
+    }
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeError::Missing => write!(f, "bridge missing"),
+            BridgeError::QueueFull => write!(f, "populator request queue is full"),
+            BridgeError::Io(e) => write!(f, "failed to write to populator: {e}"),
+            BridgeError::Timeout { kind, after } => {
+                write!(f, "{kind} timed out after {:.1}s", after.as_secs_f64())
+            }
+            BridgeError::Cancelled { kind } => write!(f, "{kind} was cancelled"),
+            BridgeError::Exited { kind } => write!(f, "populator exited before answering {kind}"),
+            BridgeError::Sidecar(e) => write!(f, "{e}"),
+            BridgeError::Protocol(e) => write!(f, "protocol error: {e}"),
+            BridgeError::Rejected { kind, reason } => write!(f, "{kind} rejected: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+// Serialized as `{ kind, message }` so the frontend can branch on the kind
+// and still show the message as-is.
+impl Serialize for BridgeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("BridgeError", 2)?;
+        state.serialize_field("kind", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// Receives the events the bridge raises: the app's windows in production,
+/// a recorder in tests.
+pub trait EventSink: Send + Sync {
+    fn emit_json(&self, event: &str, payload: Value);
+
+    /// Delivers an event meant only for the window labelled `window`. Sinks
+    /// without windows take it like any other event.
+    fn emit_json_to(&self, window: &str, event: &str, payload: Value) {
+        let _ = window;
+        self.emit_json(event, payload);
+    }
+
+    /// Delivers an event meant for a caller without a window: the socket,
+    /// the HTTP API or the CLI. The app's windows never see it.
+    fn emit_json_headless(&self, event: &str, payload: Value) {
+        self.emit_json(event, payload);
+    }
+}
+
+impl<R: Runtime> EventSink for AppHandle<R> {
+    fn emit_json(&self, event: &str, payload: Value) {
+        Emitter::emit(self, event, payload).ok();
+    }
+
+    fn emit_json_to(&self, window: &str, event: &str, payload: Value) {
+        Emitter::emit_to(self, window, event, payload).ok();
+    }
+
+    fn emit_json_headless(&self, event: &str, _payload: Value) {
+        log::debug!("not sending {event} to any window; no window owns it");
+    }
+}
+
+/// Hands every event to each of several sinks.
+pub struct Fanout(pub Vec<Arc<dyn EventSink>>);
+
+impl EventSink for Fanout {
+    fn emit_json(&self, event: &str, payload: Value) {
+        for sink in &self.0 {
+            sink.emit_json(event, payload.clone());
+        }
+    }
+
+    fn emit_json_to(&self, window: &str, event: &str, payload: Value) {
+        for sink in &self.0 {
+            sink.emit_json_to(window, event, payload.clone());
+        }
+    }
+
+    fn emit_json_headless(&self, event: &str, payload: Value) {
+        for sink in &self.0 {
+            sink.emit_json_headless(event, payload.clone());
+        }
+    }
+}
+
+impl dyn EventSink {
+    pub fn emit(&self, event: &str, payload: impl Serialize) {
+        match serde_json::to_value(payload) {
+            Ok(payload) => self.emit_json(event, payload),
+            Err(e) => log::warn!("failed to serialize {event}: {e}"),
+        }
+    }
+
+    /// Emits to `window` when the event belongs to one, and otherwise only
+    /// to sinks without windows.
+    pub fn emit_to(&self, window: Option<&str>, event: &str, payload: impl Serialize) {
+        match serde_json::to_value(payload) {
+            Ok(payload) => match window {
+                Some(window) => self.emit_json_to(window, event, payload),
+                None => self.emit_json_headless(event, payload),
+            },
+            Err(e) => log::warn!("failed to serialize {event}: {e}"),
+        }
+    }
+}
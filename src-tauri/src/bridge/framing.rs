@@ -0,0 +1,97 @@
+//! Transport framing for the populator's stdio.
+//!
+//! In line mode every message is one `\n`-terminated line. In length mode a
+//! message is sent as `#<byte length>\n<payload>\n`, so payloads may contain
+//! raw newlines and huge packets are read in one go. JSON never starts with
+//! `#`, which lets both sides accept either form at any time; only the writer
+//! has to know which one the other side understands.
+
+use std::io::{self, BufRead, Read};
+
+use serde::{Deserialize, Serialize};
+
+// Frames claiming more than this are treated as garbage and skipped.
+const MAX_FRAME_LEN: u64 = 512 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Framing {
+    #[default]
+    Line,
+    Length,
+}
+
+impl Framing {
+    pub fn encode(self, payload: &str) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(payload.len() + 16);
+        if self == Framing::Length {
+            buf.extend_from_slice(format!("#{}\n", payload.len()).as_bytes());
+        }
+        buf.extend_from_slice(payload.as_bytes());
+        buf.push(b'\n');
+        buf
+    }
+}
+
+/// Yields plain lines from a child pipe until it closes, replacing invalid
+/// UTF-8 rather than giving up on the stream.
+pub fn read_lines<R: BufRead>(mut reader: R) -> impl Iterator<Item = String> {
+    std::iter::from_fn(move || read_line(&mut reader))
+}
+
+/// Like [`read_lines`], but unpacks length-framed messages as they come.
+pub fn read_messages<R: BufRead>(mut reader: R) -> impl Iterator<Item = String> {
+    std::iter::from_fn(move || loop {
+        let line = read_line(&mut reader)?;
+        let Some(len) = frame_len(&line) else {
+            return Some(line);
+        };
+        match read_frame(&mut reader, len) {
+            Ok(Some(payload)) => return Some(payload),
+            Ok(None) => log::warn!("skipped oversized populator frame of {len} bytes"),
+            Err(_) => return None,
+        }
+    })
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> Option<String> {
+    let mut buf = Vec::new();
+    match reader.read_until(b'\n', &mut buf) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(
+            String::from_utf8_lossy(&buf)
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+        ),
+    }
+}
+
+fn frame_len(line: &str) -> Option<u64> {
+    let digits = line.strip_prefix('#')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn read_frame<R: BufRead>(reader: &mut R, len: u64) -> io::Result<Option<String>> {
+    if len > MAX_FRAME_LEN {
+        io::copy(&mut reader.by_ref().take(len), &mut io::sink())?;
+        skip_newline(reader)?;
+        return Ok(None);
+    }
+
+    let mut payload = Vec::with_capacity(len as usize);
+    reader.by_ref().take(len).read_to_end(&mut payload)?;
+    if payload.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    skip_newline(reader)?;
+    Ok(Some(String::from_utf8_lossy(&payload).into_owned()))
+}
+
+fn skip_newline<R: BufRead>(reader: &mut R) -> io::Result<()> {
+    let mut rest = Vec::new();
+    reader.read_until(b'\n', &mut rest)?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::{path::PathBuf, sync::atomic::Ordering, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{framing::Framing, lock, Bridge, BridgeError};
+
+/// Protocol revisions of the populator this shell can talk to.
+pub const SUPPORTED_PROTOCOLS: std::ops::RangeInclusive<u32> = 1..=1;
+
+// PyInstaller one-file builds unpack themselves before Python even starts.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the populator reported about itself in reply to `hello`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Handshake {
+    pub protocol: u32,
+    pub version: String,
+    pub kinds: Vec<String>,
+    /// Framing the populator agreed to; older populators only know lines.
+    #[serde(default)]
+    pub framing: Framing,
+    /// Where the populator keeps its log files, if it says.
+    #[serde(default, rename = "logDir")]
+    pub log_dir: Option<PathBuf>,
+}
+
+pub(super) enum HandshakeError {
+    /// The populator answered but can't be used with this shell.
+    Incompatible(String),
+    /// The populator never answered; worth another try.
+    Failed(BridgeError),
+}
+
+impl Bridge {
+    pub fn handshake(&self) -> Option<Handshake> {
+        lock(&self.handshake).clone()
+    }
+
+    pub(super) fn hello(&self) -> Result<Handshake, HandshakeError> {
+        let body = json!({
+            "protocol": SUPPORTED_PROTOCOLS.end(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "framing": [Framing::Length, Framing::Line],
+        });
+        let payload = match self.call("hello", body, HELLO_TIMEOUT) {
+            Ok(payload) => payload,
+            // Populators built before the handshake existed don't know `hello`
+            Err(BridgeError::Sidecar(e)) => {
+                return Err(HandshakeError::Incompatible(format!(
+                    "the populator does not support the handshake ({e})"
+                )))
+            }
+            Err(e) => return Err(HandshakeError::Failed(e)),
+        };
+
+        let handshake: Handshake = serde_json::from_value(payload).map_err(|e| {
+            HandshakeError::Incompatible(format!("malformed handshake from populator: {e}"))
+        })?;
+        if !SUPPORTED_PROTOCOLS.contains(&handshake.protocol) {
+            return Err(HandshakeError::Incompatible(format!(
+                "populator {} speaks protocol {}, but DataSmith {} supports {}..={}",
+                handshake.version,
+                handshake.protocol,
+                env!("CARGO_PKG_VERSION"),
+                SUPPORTED_PROTOCOLS.start(),
+                SUPPORTED_PROTOCOLS.end(),
+            )));
+        }
+        if handshake.version != env!("CARGO_PKG_VERSION") {
+            log::warn!(
+                "populator version {} differs from DataSmith {}",
+                handshake.version,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+
+        self.framed
+            .store(handshake.framing == Framing::Length, Ordering::SeqCst);
+        if let Some(dir) = &handshake.log_dir {
+            self.watch_populator_logs(dir.clone());
+        }
+        *lock(&self.handshake) = Some(handshake.clone());
+        Ok(handshake)
+    }
+}
@@ -0,0 +1,456 @@
+//! Loopback HTTP API that lets test suites drive the running app.
+//!
+//! `POST /rpc` takes JSON-RPC 2.0 calls, single or batched, and translates
+//! each into a populator request. `GET /events` streams generation progress
+//! and bridge status as server-sent events. Every request must carry
+//! `Authorization: Bearer <token>`; the token is minted per session and
+//! written with the address to a file only the owning user can read.
+
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{
+    lock, Bridge, BridgeError, EventSink, RequestOptions, Shared, DONE_EVENT, PROGRESS_EVENT,
+    STATUS_EVENT,
+};
+use crate::protocol::{DbCreds, JobRef, PacketExport, PacketRef, TableSpec};
+
+/// Port to listen on at startup when set; `0` picks a free one.
+pub const HTTP_ENV: &str = "DATASMITH_HTTP";
+/// The label the [`Policy`](super::Policy) checks API calls under.
+pub const HTTP_CALLER: &str = "http";
+
+const STREAMED_EVENTS: &[&str] = &[PROGRESS_EVENT, DONE_EVENT, STATUS_EVENT];
+const MAX_HEADERS: usize = 64;
+const MAX_BODY: usize = 1 << 20;
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// Proxies and clients drop idle streams; a comment line keeps them open.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const BRIDGE_ERROR: i64 = -32000;
+
+/// Where and how to reach the API; also the contents of the info file.
+#[derive(Clone, Debug, Serialize)]
+pub struct HttpInfo {
+    pub url: String,
+    pub token: String,
+}
+
+/// Forwards bridge events to the connected `/events` clients.
+#[derive(Default)]
+pub struct EventStream {
+    clients: Mutex<Vec<mpsc::Sender<String>>>,
+}
+
+impl EventSink for EventStream {
+    fn emit_json(&self, event: &str, payload: Value) {
+        if !STREAMED_EVENTS.contains(&event) {
+            return;
+        }
+        let frame = format!("event: {event}\ndata: {payload}\n\n");
+        // Clients that went away are dropped here
+        lock(&self.clients).retain(|client| client.send(frame.clone()).is_ok());
+    }
+}
+
+struct Server {
+    bridge: Shared,
+    token: String,
+    events: Arc<EventStream>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct Call {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<BridgeError> for RpcError {
+    fn from(e: BridgeError) -> Self {
+        RpcError {
+            code: BRIDGE_ERROR,
+            message: e.to_string(),
+            data: serde_json::to_value(&e).ok(),
+        }
+    }
+}
+
+impl Bridge {
+    /// Starts the API on `127.0.0.1:port` and writes its [`HttpInfo`] to
+    /// `info_file`. The returned stream has to be fed the bridge's events.
+    pub fn serve_http(
+        self: &Arc<Self>,
+        port: u16,
+        info_file: PathBuf,
+    ) -> io::Result<Arc<EventStream>> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let info = HttpInfo {
+            url: format!("http://{}", listener.local_addr()?),
+            token: mint_token()?,
+        };
+        write_info(&info_file, &info)?;
+        *lock(&self.http) = Some((info.clone(), info_file));
+
+        let events = Arc::new(EventStream::default());
+        let server = Arc::new(Server {
+            bridge: self.clone(),
+            token: info.token,
+            events: events.clone(),
+        });
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let server = server.clone();
+                        thread::spawn(move || server.serve(stream));
+                    }
+                    Err(e) => log::warn!("failed to accept http client: {e}"),
+                }
+            }
+        });
+        Ok(events)
+    }
+
+    pub fn http_info(&self) -> Option<HttpInfo> {
+        lock(&self.http).as_ref().map(|(info, _)| info.clone())
+    }
+
+    pub(super) fn remove_http_info(&self) {
+        if let Some((_, path)) = lock(&self.http).take() {
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+fn mint_token() -> io::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn write_info(path: &Path, info: &HttpInfo) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // A file left by an older session keeps its old mode otherwise
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options.open(path)?;
+    serde_json::to_writer_pretty(&mut file, info)?;
+    Ok(())
+}
+
+impl Server {
+    fn serve(&self, stream: TcpStream) {
+        stream.set_read_timeout(Some(READ_TIMEOUT)).ok();
+        let request = match stream
+            .try_clone()
+            .and_then(|reader| read_request(&mut BufReader::new(reader)))
+        {
+            Ok(request) => request,
+            Err(e) => {
+                respond(
+                    &stream,
+                    "400 Bad Request",
+                    &json!({ "error": e.to_string() }),
+                );
+                return;
+            }
+        };
+
+        if !self.authorized(&request) {
+            respond(
+                &stream,
+                "401 Unauthorized",
+                &json!({ "error": "missing or invalid bearer token" }),
+            );
+            return;
+        }
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/rpc") => match self.rpc(&request.body) {
+                Some(reply) => respond(&stream, "200 OK", &reply),
+                None => respond_empty(&stream, "204 No Content"),
+            },
+            ("GET", "/events") => self.stream_events(stream),
+            (_, "/rpc" | "/events") => respond(
+                &stream,
+                "405 Method Not Allowed",
+                &json!({ "error": "method not allowed" }),
+            ),
+            _ => respond(&stream, "404 Not Found", &json!({ "error": "not found" })),
+        }
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let Some(token) = request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        // Compare in constant time so the token can't be guessed byte by byte
+        token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// Answers a call or batch, or returns `None` when it held only notifications.
+    fn rpc(&self, body: &[u8]) -> Option<Value> {
+        let message = match serde_json::from_slice::<Value>(body) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(reply(
+                    Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, e.to_string())),
+                ))
+            }
+        };
+        match message {
+            Value::Array(calls) if calls.is_empty() => Some(reply(
+                Value::Null,
+                Err(RpcError::new(INVALID_REQUEST, "empty batch")),
+            )),
+            Value::Array(calls) => {
+                let replies: Vec<Value> = calls
+                    .into_iter()
+                    .filter_map(|call| self.answer(call))
+                    .collect();
+                (!replies.is_empty()).then_some(Value::Array(replies))
+            }
+            call => self.answer(call),
+        }
+    }
+
+    fn answer(&self, call: Value) -> Option<Value> {
+        let call = match serde_json::from_value::<Call>(call) {
+            Ok(call) if call.jsonrpc == "2.0" => call,
+            Ok(call) => {
+                let error = RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+                return Some(reply(call.id.unwrap_or_default(), Err(error)));
+            }
+            Err(e) => {
+                return Some(reply(
+                    Value::Null,
+                    Err(RpcError::new(INVALID_REQUEST, e.to_string())),
+                ))
+            }
+        };
+        let result =
+            tauri::async_runtime::block_on(dispatch(&self.bridge, &call.method, call.params));
+        call.id.map(|id| reply(id, result))
+    }
+
+    fn stream_events(&self, mut stream: TcpStream) {
+        let (tx, rx) = mpsc::channel();
+        lock(&self.events.clients).push(tx);
+        let headers = "HTTP/1.1 200 OK\r\n\
+                       Content-Type: text/event-stream\r\n\
+                       Cache-Control: no-cache\r\n\
+                       Connection: keep-alive\r\n\r\n";
+        if stream.write_all(headers.as_bytes()).is_err() {
+            return;
+        }
+        loop {
+            let frame = match rx.recv_timeout(KEEPALIVE) {
+                Ok(frame) => frame,
+                Err(mpsc::RecvTimeoutError::Timeout) => ": keepalive\n\n".into(),
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            };
+            if stream
+                .write_all(frame.as_bytes())
+                .and_then(|_| stream.flush())
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+async fn dispatch(bridge: &Bridge, method: &str, params: Value) -> Result<Value, RpcError> {
+    let result: Result<Value, BridgeError> = match method {
+        // Saved connections can be reopened without their password
+        "connect" => {
+            let creds: DbCreds = parse_params(params)?;
+            let kind = match (&creds.password, &creds.password_source) {
+                (None, None) => "set_db_reconnect",
+                _ => "set_db_connect",
+            };
+            forward(bridge, kind, creds).await
+        }
+        "listTables" => forward(bridge, "get_db_tables", Value::Null).await,
+        "generate" => {
+            let spec: TableSpec = parse_params(params)?;
+            forward(bridge, "get_gen_packets", spec).await
+        }
+        "poll" => {
+            let job: JobRef = parse_params(params)?;
+            forward(bridge, "poll_gen_status", job).await
+        }
+        "insert" => {
+            let packet: PacketRef = parse_params(params)?;
+            forward(bridge, "set_db_insert", packet).await
+        }
+        "commit" => forward(bridge, "set_db_commit", Value::Null).await,
+        "rollback" => forward(bridge, "set_db_rollback", Value::Null).await,
+        "export" => {
+            let export: PacketExport = parse_params(params)?;
+            forward(bridge, "set_db_export", export).await
+        }
+        method => {
+            return Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {method}"),
+            ))
+        }
+    };
+    Ok(result?)
+}
+
+// Checked like a window's requests, so the API can't send what no window may
+async fn forward(bridge: &Bridge, kind: &str, body: impl Serialize) -> Result<Value, BridgeError> {
+    let body = serde_json::to_value(body).map_err(|e| BridgeError::Protocol(e.to_string()))?;
+    bridge.check_policy(HTTP_CALLER, kind, &body)?;
+    bridge.request(kind, body, RequestOptions::default()).await
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn reply(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Request> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+    let method = method.to_string();
+    // Query strings carry nothing we use
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("connection closed inside headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| invalid("malformed header"))?;
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    let length = match headers.get("content-length") {
+        Some(length) => length
+            .parse::<usize>()
+            .map_err(|_| invalid("invalid content-length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn respond(mut stream: &TcpStream, status: &str, body: &Value) {
+    let body = body.to_string();
+    // A client that hung up early just doesn't get its response
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .and_then(|_| stream.flush())
+    .ok();
+}
+
+fn respond_empty(mut stream: &TcpStream, status: &str) {
+    write!(stream, "HTTP/1.1 {status}\r\nConnection: close\r\n\r\n")
+        .and_then(|_| stream.flush())
+        .ok();
+}
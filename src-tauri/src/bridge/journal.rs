@@ -0,0 +1,114 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{lock, redact::redact_passwords, Bridge, BridgeError};
+
+/// Starts journaling as soon as the bridge comes up, so the handshake is
+/// part of the recording.
+pub const JOURNAL_ENV: &str = "DATASMITH_JOURNAL";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Written to the populator's stdin.
+    Out,
+    /// Read from the populator's stdout.
+    In,
+}
+
+/// One line of a journal file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+    pub dir: Direction,
+    /// The message as JSON, or as a string when it isn't JSON (`exit`).
+    pub msg: Value,
+}
+
+/// Opt-in JSONL recording of everything exchanged with the populator.
+pub struct Journal {
+    dir: PathBuf,
+    file: Mutex<Option<(PathBuf, File)>>,
+}
+
+impl Journal {
+    pub fn new(dir: PathBuf) -> Self {
+        Journal {
+            dir,
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Opens a new journal file, or keeps using the one already open.
+    pub fn start(&self) -> io::Result<PathBuf> {
+        let mut file = lock(&self.file);
+        if let Some((path, _)) = file.as_ref() {
+            return Ok(path.clone());
+        }
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("bridge-{}.jsonl", now_ms()));
+        *file = Some((path.clone(), File::create(&path)?));
+        Ok(path)
+    }
+
+    pub fn stop(&self) -> Option<PathBuf> {
+        lock(&self.file).take().map(|(path, _)| path)
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        lock(&self.file).as_ref().map(|(path, _)| path.clone())
+    }
+
+    pub fn record(&self, dir: Direction, line: &str) {
+        let mut file = lock(&self.file);
+        let Some((path, writer)) = file.as_mut() else {
+            return;
+        };
+
+        let mut msg = serde_json::from_str(line).unwrap_or_else(|_| Value::from(line));
+        redact_passwords(&mut msg);
+        let entry = Entry {
+            at: now_ms(),
+            dir,
+            msg,
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(io::Error::from)
+            .and_then(|entry| writeln!(writer, "{entry}"));
+        if let Err(e) = written {
+            log::warn!("stopped journal {}: {e}", path.display());
+            file.take();
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+impl Bridge {
+    pub fn start_journal(&self) -> Result<PathBuf, BridgeError> {
+        self.journal
+            .start()
+            .map_err(|e| BridgeError::Io(e.to_string()))
+    }
+
+    pub fn stop_journal(&self) -> Option<PathBuf> {
+        self.journal.stop()
+    }
+
+    pub fn journal_path(&self) -> Option<PathBuf> {
+        self.journal.path()
+    }
+}
@@ -0,0 +1,344 @@
+//! One live timeline of the shell's log, the populator's log files and its
+//! stderr, pushed to the windows following it.
+//!
+//! Lines from every source go through a single bounded queue, so the order
+//! of the timeline is the order the shell saw them in. A thread drains it in
+//! short batches, keeps a backlog for windows that start following later,
+//! and sends each follower the entries its filter lets through.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::Level;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use super::{lock, Bridge, BridgeError, EventSink};
+use crate::logging::{self, LogRecord};
+
+pub const LOG_EVENT: &str = "log-entries";
+
+const QUEUE: usize = 4096;
+const BACKLOG: usize = 2000;
+// Long enough to coalesce a burst, short enough to feel live
+const BATCH: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    /// The shell's own log.
+    Shell,
+    /// The populator's log files.
+    Populator,
+    /// What the populator writes to stderr.
+    Stderr,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LogEntry {
+    /// Position in the timeline; later entries have higher numbers.
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+    pub source: LogSource,
+    pub level: String,
+    /// The shell's log target, or the name of the populator's log file.
+    pub target: String,
+    pub message: String,
+}
+
+/// Which entries a window wants.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilter {
+    /// The least severe level to include, e.g. `warn`.
+    pub level: Option<String>,
+    /// Every source when empty.
+    #[serde(default)]
+    pub sources: Vec<LogSource>,
+}
+
+struct Follow {
+    level: Option<Level>,
+    sources: Vec<LogSource>,
+}
+
+impl Follow {
+    fn wants(&self, entry: &LogEntry) -> bool {
+        (self.sources.is_empty() || self.sources.contains(&entry.source))
+            && self.level.map_or(true, |level| {
+                Level::from_str(&entry.level).is_ok_and(|own| own <= level)
+            })
+    }
+}
+
+pub struct LogStream {
+    tx: SyncSender<LogEntry>,
+    rx: Mutex<Option<Receiver<LogEntry>>>,
+    backlog: Mutex<VecDeque<LogEntry>>,
+    followers: Mutex<HashMap<String, Follow>>,
+    watcher: Mutex<Option<(PathBuf, RecommendedWatcher)>>,
+}
+
+impl Default for LogStream {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::sync_channel(QUEUE);
+        LogStream {
+            tx,
+            rx: Mutex::new(Some(rx)),
+            backlog: Mutex::new(VecDeque::with_capacity(BACKLOG)),
+            followers: Mutex::new(HashMap::new()),
+            watcher: Mutex::new(None),
+        }
+    }
+}
+
+impl LogStream {
+    pub(super) fn push(&self, source: LogSource, level: &str, target: &str, message: String) {
+        push(&self.tx, source, level, target, message);
+    }
+}
+
+/// Queues a line for the timeline, dropping it if nobody drains the queue.
+fn push(tx: &SyncSender<LogEntry>, source: LogSource, level: &str, target: &str, message: String) {
+    let entry = LogEntry {
+        seq: 0,
+        at: now_ms(),
+        source,
+        level: level.to_string(),
+        target: target.to_string(),
+        message,
+    };
+    tx.try_send(entry).ok();
+}
+
+/// The level of a line Python's `logging` wrote, as `[WARNING]` or
+/// `WARNING:`.
+pub(super) fn python_level(line: &str, default: &'static str) -> &'static str {
+    const LEVELS: &[(&str, &str)] = &[
+        ("CRITICAL", "ERROR"),
+        ("ERROR", "ERROR"),
+        ("WARNING", "WARN"),
+        ("INFO", "INFO"),
+        ("DEBUG", "DEBUG"),
+    ];
+    LEVELS
+        .iter()
+        .find(|(python, _)| {
+            line.contains(&format!("[{python}]")) || line.starts_with(&format!("{python}:"))
+        })
+        .map_or(default, |&(_, level)| level)
+}
+
+/// Follows the `*.log` files in the populator's log directory from their
+/// current ends.
+struct Tailer {
+    offsets: HashMap<PathBuf, u64>,
+    partial: HashMap<PathBuf, String>,
+}
+
+impl Tailer {
+    fn new(dir: &Path) -> Self {
+        let offsets = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| is_log(&entry.path()))
+            .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?.len())))
+            .collect();
+        Tailer {
+            offsets,
+            partial: HashMap::new(),
+        }
+    }
+
+    fn read(&mut self, path: &Path, tx: &SyncSender<LogEntry>) {
+        let Ok(mut file) = File::open(path) else {
+            self.offsets.remove(path);
+            self.partial.remove(path);
+            return;
+        };
+        let len = file.metadata().map_or(0, |meta| meta.len());
+        let offset = self.offsets.entry(path.to_path_buf()).or_insert(0);
+        // Cleared or replaced since the last read
+        if len < *offset {
+            *offset = 0;
+            self.partial.remove(path);
+        }
+        let mut added = Vec::new();
+        if file.seek(SeekFrom::Start(*offset)).is_err() || file.read_to_end(&mut added).is_err() {
+            return;
+        }
+        *offset += added.len() as u64;
+
+        let partial = self.partial.entry(path.to_path_buf()).or_default();
+        partial.push_str(&String::from_utf8_lossy(&added));
+        let Some(end) = partial.rfind('\n') else {
+            return;
+        };
+        let rest = partial.split_off(end + 1);
+        let complete = std::mem::replace(partial, rest);
+        let target = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let target = target.trim_end_matches(".log");
+        for line in complete.lines().map(str::trim_end) {
+            if !line.is_empty() {
+                let level = python_level(line, "INFO");
+                push(tx, LogSource::Populator, level, target, line.to_string());
+            }
+        }
+    }
+}
+
+fn is_log(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "log")
+}
+
+impl Bridge {
+    /// Starts sending followers the merged timeline through `events`. Only
+    /// the first call does anything.
+    pub fn stream_logs(self: &Arc<Self>, events: Arc<dyn EventSink>) {
+        let Some(rx) = lock(&self.logs.rx).take() else {
+            return;
+        };
+        let tx = self.logs.tx.clone();
+        logging::follow(move |record: &LogRecord| {
+            let entry = LogEntry {
+                seq: 0,
+                at: record.at,
+                source: LogSource::Shell,
+                level: record.level.clone(),
+                target: record.target.clone(),
+                message: record.message.clone(),
+            };
+            tx.try_send(entry).ok();
+        });
+
+        let bridge = Arc::downgrade(self);
+        thread::Builder::new()
+            .name("log-stream".into())
+            .spawn(move || {
+                let mut seq = 0;
+                loop {
+                    let first = match rx.recv_timeout(Duration::from_secs(1)) {
+                        Ok(entry) => entry,
+                        Err(RecvTimeoutError::Timeout) if bridge.strong_count() > 0 => continue,
+                        Err(_) => return,
+                    };
+                    let deadline = Instant::now() + BATCH;
+                    let mut batch = vec![first];
+                    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                        match rx.recv_timeout(left) {
+                            Ok(entry) => batch.push(entry),
+                            Err(_) => break,
+                        }
+                    }
+                    let Some(bridge) = bridge.upgrade() else {
+                        return;
+                    };
+                    for entry in &mut batch {
+                        seq += 1;
+                        entry.seq = seq;
+                    }
+                    bridge.publish_logs(&*events, batch);
+                }
+            })
+            .expect("failed to spawn log stream thread");
+    }
+
+    fn publish_logs(&self, events: &dyn EventSink, batch: Vec<LogEntry>) {
+        let followers = lock(&self.logs.followers);
+        for (window, follow) in followers.iter() {
+            let wanted: Vec<_> = batch.iter().filter(|e| follow.wants(e)).collect();
+            if !wanted.is_empty() {
+                events.emit_to(Some(window.as_str()), LOG_EVENT, wanted);
+            }
+        }
+        let mut backlog = lock(&self.logs.backlog);
+        for entry in batch {
+            if backlog.len() == BACKLOG {
+                backlog.pop_front();
+            }
+            backlog.push_back(entry);
+        }
+    }
+
+    /// Sends `window` the entries `filter` lets through from now on, and
+    /// returns the ones it missed, oldest first.
+    pub fn follow_logs(
+        &self,
+        window: &str,
+        filter: LogFilter,
+    ) -> Result<Vec<LogEntry>, BridgeError> {
+        let level = filter
+            .level
+            .as_deref()
+            .map(Level::from_str)
+            .transpose()
+            .map_err(|_| BridgeError::Rejected {
+                kind: "logs_follow".into(),
+                reason: format!("unknown log level {:?}", filter.level.unwrap_or_default()),
+            })?;
+        let follow = Follow {
+            level,
+            sources: filter.sources,
+        };
+        // Held across both so no batch falls between the backlog and the events
+        let mut followers = lock(&self.logs.followers);
+        let missed = lock(&self.logs.backlog)
+            .iter()
+            .filter(|entry| follow.wants(entry))
+            .cloned()
+            .collect();
+        followers.insert(window.to_string(), follow);
+        Ok(missed)
+    }
+
+    pub fn unfollow_logs(&self, window: &str) {
+        lock(&self.logs.followers).remove(window);
+    }
+
+    /// Tails the populator's log files in `dir`, unless they already are.
+    pub(super) fn watch_populator_logs(&self, dir: PathBuf) {
+        let mut watcher = lock(&self.logs.watcher);
+        if watcher.as_ref().is_some_and(|(watched, _)| *watched == dir) {
+            return;
+        }
+        watcher.take();
+
+        let tx = self.logs.tx.clone();
+        let mut tailer = Tailer::new(&dir);
+        let watching = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            for path in event.paths.iter().filter(|path| is_log(path)) {
+                tailer.read(path, &tx);
+            }
+        })
+        .and_then(|mut w| w.watch(&dir, RecursiveMode::NonRecursive).map(|()| w));
+        match watching {
+            Ok(w) => *watcher = Some((dir, w)),
+            Err(e) => log::warn!("failed to watch populator logs in {}: {e}", dir.display()),
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
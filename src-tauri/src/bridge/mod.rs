@@ -0,0 +1,238 @@
+mod audit;
+mod builder;
+mod credentials;
+mod diagnostics;
+mod error;
+mod events;
+mod framing;
+mod handshake;
+mod http;
+mod journal;
+mod logstream;
+mod pending;
+mod policy;
+mod progress;
+mod redact;
+mod replay;
+mod rotate;
+mod shutdown;
+#[cfg(unix)]
+mod socket;
+mod sql_guard;
+mod stderr;
+mod supervisor;
+mod timeouts;
+mod vault;
+mod writer;
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::ChildStdin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread::JoinHandle,
+};
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use audit::Audit;
+use credentials::Sources;
+use journal::Journal;
+use logstream::LogStream;
+use pending::{Abandoned, Pending};
+use progress::Tracker;
+use stderr::StderrLog;
+use vault::Vault;
+
+pub use audit::{AuditEntry, AuditQuery, Connection, Outcome};
+pub use builder::BridgeBuilder;
+pub use credentials::confirm_credential_source;
+pub use error::BridgeError;
+pub use events::{EventSink, Fanout};
+pub use framing::Framing;
+pub use handshake::Handshake;
+pub use http::{EventStream, HttpInfo, HTTP_CALLER, HTTP_ENV};
+pub use journal::JOURNAL_ENV;
+pub use logstream::{LogEntry, LogFilter, LogSource, LOG_EVENT};
+pub use pending::RequestOptions;
+pub use policy::Policy;
+pub use progress::{DONE_EVENT, PROGRESS_EVENT};
+pub use redact::redact_sql;
+pub use replay::{serve_replay, REPLAY_ENV, REPLAY_FLAG};
+pub(crate) use rotate::RotatingFile;
+pub use shutdown::{confirm_close, shutdown_on_exit, MAIN_WINDOW};
+#[cfg(unix)]
+pub use socket::SOCKET_ENV;
+pub use sql_guard::{classify_sql, confirm_sql_request, SqlRisk, SqlVerdict};
+pub use supervisor::{populator_sidecar, spawn_supervisor, Sidecar};
+pub use timeouts::Timeouts;
+pub use vault::{VaultStatus, DEFAULT_LOCK_AFTER};
+pub use writer::QueueStats;
+
+pub const STATUS_EVENT: &str = "bridge-status";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeState {
+    Starting,
+    Ready,
+    Crashed,
+    Restarting,
+    /// The populator speaks a protocol this shell doesn't; it won't be restarted.
+    Incompatible,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BridgeStatus {
+    pub state: BridgeState,
+    pub restarts: u32,
+    pub code: Option<i32>,
+    pub message: Option<String>,
+}
+
+/// Handle to the populator sidecar shared between the supervisor and commands.
+pub struct Bridge {
+    writer: Mutex<Option<mpsc::Sender<String>>>,
+    pid: Mutex<Option<u32>>,
+    status: Mutex<BridgeStatus>,
+    shutdown: AtomicBool,
+    closing: AtomicBool,
+    pending: Mutex<HashMap<String, Pending>>,
+    abandoned: Mutex<Abandoned>,
+    next_id: AtomicU64,
+    timeouts: Mutex<Timeouts>,
+    policy: Mutex<Policy>,
+    active_job: Mutex<Option<ActiveJob>>,
+    progress: Mutex<Option<Tracker>>,
+    handshake: Mutex<Option<Handshake>>,
+    framed: Arc<AtomicBool>,
+    stderr: StderrLog,
+    logs: LogStream,
+    journal: Journal,
+    audit: Audit,
+    vault: Vault,
+    sources: Sources,
+    socket: Mutex<Option<PathBuf>>,
+    http: Mutex<Option<(HttpInfo, PathBuf)>>,
+}
+
+pub type Shared = Arc<Bridge>;
+
+/// The generation job running in the populator and the window that started it.
+struct ActiveJob {
+    id: String,
+    window: Option<String>,
+}
+
+impl Bridge {
+    pub fn new(log_dir: PathBuf) -> Shared {
+        Arc::new(Bridge {
+            writer: Mutex::new(None),
+            pid: Mutex::new(None),
+            status: Mutex::new(BridgeStatus {
+                state: BridgeState::Starting,
+                restarts: 0,
+                code: None,
+                message: None,
+            }),
+            shutdown: AtomicBool::new(false),
+            closing: AtomicBool::new(false),
+            pending: Mutex::new(HashMap::new()),
+            abandoned: Mutex::new(Abandoned::default()),
+            next_id: AtomicU64::new(0),
+            timeouts: Mutex::new(Timeouts::default()),
+            policy: Mutex::new(Policy::default()),
+            active_job: Mutex::new(None),
+            progress: Mutex::new(None),
+            handshake: Mutex::new(None),
+            framed: Arc::new(AtomicBool::new(false)),
+            journal: Journal::new(log_dir.join("journal")),
+            audit: Audit::new(log_dir.join("audit.jsonl")),
+            vault: Vault::default(),
+            sources: Sources::default(),
+            stderr: StderrLog::new(log_dir),
+            logs: LogStream::default(),
+            socket: Mutex::new(None),
+            http: Mutex::new(None),
+        })
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        *lock(&self.pid)
+    }
+
+    pub fn status(&self) -> BridgeStatus {
+        lock(&self.status).clone()
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        lock(&self.timeouts).clone()
+    }
+
+    pub fn set_timeouts(&self, timeouts: Timeouts) {
+        *lock(&self.timeouts) = timeouts;
+    }
+
+    pub fn stderr_tail(&self, lines: usize) -> Vec<String> {
+        self.stderr.tail(lines)
+    }
+
+    /// Where scripts can reach the bridge, if it is listening for them.
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        lock(&self.socket).clone()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    fn attach(&self, stdin: Option<ChildStdin>, pid: u32) -> Option<JoinHandle<()>> {
+        // Every new child starts out in line mode until the handshake says otherwise
+        self.framed.store(false, Ordering::SeqCst);
+        let (tx, handle) = stdin
+            .map(|stdin| writer::spawn_writer(stdin, self.framed.clone()))
+            .unzip();
+        *lock(&self.writer) = tx;
+        *lock(&self.pid) = Some(pid);
+        handle
+    }
+
+    fn detach(&self) {
+        // Closing the queue ends the writer thread, which closes stdin
+        lock(&self.writer).take();
+        lock(&self.pid).take();
+        // Dropping the senders fails every request the dead child never answered
+        lock(&self.pending).clear();
+        lock(&self.active_job).take();
+        lock(&self.progress).take();
+        lock(&self.handshake).take();
+        self.audit.reset();
+    }
+
+    fn set_status(&self, events: &dyn EventSink, status: BridgeStatus) {
+        match status.state {
+            BridgeState::Incompatible => log::error!(
+                "populator is incompatible: {}",
+                status.message.as_deref().unwrap_or("")
+            ),
+            BridgeState::Crashed => log::warn!(
+                "populator crashed (code {:?}): {}",
+                status.code,
+                status.message.as_deref().unwrap_or("")
+            ),
+            state => log::info!("populator {:?}", state),
+        }
+        *lock(&self.status) = status.clone();
+        events.emit(STATUS_EVENT, status);
+    }
+}
+
+/// Locks `mutex` even if a thread panicked while holding it; bridge state
+/// stays usable rather than poisoning every later command.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
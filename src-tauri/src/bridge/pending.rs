@@ -0,0 +1,318 @@
+use std::{collections::VecDeque, sync::atomic::Ordering, time::Duration};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use super::{credentials::SourceTicket, lock, ActiveJob, Bridge, BridgeError};
+use crate::protocol::camelize;
+
+// Kinds whose work keeps running in the sidecar after the request is gone.
+const GENERATION_KINDS: &[&str] = &["get_gen_packets", "poll_gen_status"];
+// Kinds only the shell sends, as it takes passwords over from the registry.
+const SHELL_KINDS: &[&str] = &["get_pref_passwords", "set_pref_forget_password"];
+// Prefix of the ids of requests nobody waits on, whose replies are dropped.
+const UNAWAITED: &str = "unawaited-";
+// How many abandoned requests still have their late replies routed.
+const ABANDONED_CAPACITY: usize = 64;
+
+pub(super) struct Pending {
+    kind: String,
+    window: Option<String>,
+    tx: oneshot::Sender<Result<Value, BridgeError>>,
+}
+
+/// Windows of requests that gave up waiting, newest last.
+#[derive(Default)]
+pub(super) struct Abandoned(VecDeque<(String, String)>);
+
+impl Abandoned {
+    fn push(&mut self, id: String, window: String) {
+        if self.0.len() == ABANDONED_CAPACITY {
+            self.0.pop_front();
+        }
+        self.0.push_back((id, window));
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    /// Caller-chosen id, usable as a handle for [`Bridge::cancel`].
+    pub id: Option<String>,
+    /// Overrides the configured timeout for this request's kind.
+    pub timeout_ms: Option<u64>,
+    /// Label of the window that issued the request; events it causes go to
+    /// that window only. Set by the shell, never by the caller.
+    #[serde(skip)]
+    pub window: Option<String>,
+}
+
+impl RequestOptions {
+    pub fn from_window(label: &str) -> Self {
+        RequestOptions {
+            window: Some(label.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Removes a pending entry when the request future completes or is dropped,
+/// so abandoned requests never leak.
+struct PendingGuard<'a> {
+    bridge: &'a Bridge,
+    id: String,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        let pending = lock(&self.bridge.pending).remove(&self.id);
+        if let Some(pending) = pending {
+            self.bridge.abandon(&self.id, pending.window);
+        }
+    }
+}
+
+impl Bridge {
+    /// Sends `{id, kind, body}` to the populator and waits for the response
+    /// carrying the same id, returning its payload or error.
+    pub async fn request(
+        &self,
+        kind: &str,
+        body: Value,
+        mut opts: RequestOptions,
+    ) -> Result<Value, BridgeError> {
+        let reserved = opts
+            .id
+            .as_deref()
+            .is_some_and(|id| id.starts_with(UNAWAITED));
+        let id = opts
+            .id
+            .get_or_insert_with(|| self.next_id.fetch_add(1, Ordering::SeqCst).to_string())
+            .clone();
+        let draft = self.audit.begin(kind, &body, opts.window.as_deref(), &id);
+        let res = if SHELL_KINDS.contains(&kind) {
+            Err(BridgeError::Rejected {
+                kind: kind.into(),
+                reason: "only the shell sends this request".into(),
+            })
+        } else if reserved {
+            // Their replies would be dropped as unawaited
+            Err(BridgeError::Rejected {
+                kind: kind.into(),
+                reason: format!("request ids starting with {UNAWAITED} are reserved"),
+            })
+        } else {
+            self.exchange(kind, body, opts).await
+        };
+        draft.finish(&res);
+        res
+    }
+
+    async fn exchange(
+        &self,
+        kind: &str,
+        mut body: Value,
+        opts: RequestOptions,
+    ) -> Result<Value, BridgeError> {
+        if let Some(window) = &opts.window {
+            lock(&self.policy).check(window, kind, &body)?;
+        }
+        let source = self.resolve_password(kind, &mut body).await?;
+        // A password read from a source is never kept
+        let ticket = match source {
+            Some(SourceTicket::Remember(..)) => None,
+            _ => self.vault.prepare(kind, &mut body)?,
+        };
+
+        let mut payload = self.round_trip(kind, body, opts).await?;
+        if let Some(creds) = self.vault.settle(kind, ticket, &mut payload) {
+            self.forget_registry_password(&creds).await;
+        }
+        self.sources.settle(source);
+        Ok(payload)
+    }
+
+    /// Sends one request as it is and waits for its reply, without the
+    /// checks and credential handling of [`Bridge::request`].
+    pub(super) async fn round_trip(
+        &self,
+        kind: &str,
+        body: Value,
+        opts: RequestOptions,
+    ) -> Result<Value, BridgeError> {
+        let id = match opts.id {
+            Some(id) => id,
+            None => self.next_id.fetch_add(1, Ordering::SeqCst).to_string(),
+        };
+        let timeout = match opts.timeout_ms {
+            Some(ms) => Duration::from_millis(ms),
+            None => lock(&self.timeouts).for_kind(kind),
+        };
+
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = lock(&self.pending);
+            if pending.contains_key(&id) {
+                return Err(BridgeError::Rejected {
+                    kind: kind.into(),
+                    reason: format!("request id {id} is already in use"),
+                });
+            }
+            let kind = kind.to_string();
+            let window = opts.window;
+            pending.insert(id.clone(), Pending { kind, window, tx });
+        }
+        let _guard = PendingGuard {
+            bridge: self,
+            id: id.clone(),
+        };
+
+        let line = json!({ "id": id, "kind": kind, "body": body }).to_string();
+        let exchange = async {
+            self.enqueue(line).await?;
+            match rx.await {
+                Ok(res) => res,
+                Err(_) => Err(BridgeError::Exited { kind: kind.into() }),
+            }
+        };
+        let res = tokio::time::timeout(timeout, exchange)
+            .await
+            .map_err(|_| BridgeError::Timeout {
+                kind: kind.into(),
+                after: timeout,
+            })??;
+        into_result(res)
+    }
+
+    /// [`Bridge::request`] with a typed body and reply; the reply's keys are
+    /// camelized before it is deserialized.
+    pub async fn request_as<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        body: impl Serialize,
+    ) -> Result<T, BridgeError> {
+        self.request_as_with(kind, body, RequestOptions::default())
+            .await
+    }
+
+    pub async fn request_as_with<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        body: impl Serialize,
+        opts: RequestOptions,
+    ) -> Result<T, BridgeError> {
+        let body = serde_json::to_value(body).map_err(|e| BridgeError::Protocol(e.to_string()))?;
+        let payload = self.request(kind, body, opts).await?;
+        serde_json::from_value(camelize(payload))
+            .map_err(|e| BridgeError::Protocol(format!("unexpected {kind} response: {e}")))
+    }
+
+    /// Blocking variant of [`Bridge::request`] for callers outside the async
+    /// runtime, such as the close handler.
+    pub fn call(&self, kind: &str, body: Value, timeout: Duration) -> Result<Value, BridgeError> {
+        let opts = RequestOptions {
+            timeout_ms: Some(timeout.as_millis() as u64),
+            ..Default::default()
+        };
+        tauri::async_runtime::block_on(self.request(kind, body, opts))
+    }
+
+    /// Fails the pending request `id` with [`BridgeError::Cancelled`]. When
+    /// `id` belongs to a generation request or is the running generation job,
+    /// the sidecar is told to stop generating as well. A `window` can only
+    /// cancel what it sent itself; without one, anything can be cancelled.
+    pub fn cancel(&self, id: &str, window: Option<&str>) -> bool {
+        let owns = |owner: &Option<String>| window.map_or(true, |w| owner.as_deref() == Some(w));
+        let pending = {
+            let mut pending = lock(&self.pending);
+            match pending.get(id) {
+                Some(entry) if owns(&entry.window) => pending.remove(id),
+                _ => None,
+            }
+        };
+        let mut stop_generation = false;
+        let cancelled = match pending {
+            Some(Pending { kind, window, tx }) => {
+                stop_generation = GENERATION_KINDS.contains(&kind.as_str());
+                self.abandon(id, window);
+                tx.send(Err(BridgeError::Cancelled { kind })).ok();
+                true
+            }
+            None => false,
+        };
+
+        let mut active_job = lock(&self.active_job);
+        if active_job
+            .as_ref()
+            .is_some_and(|job| job.id == id && owns(&job.window))
+        {
+            stop_generation = true;
+        }
+        if stop_generation {
+            active_job.take();
+            let unawaited = format!("{UNAWAITED}{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+            let line = json!({ "id": unawaited, "kind": "clear_gen_packets" }).to_string();
+            if let Err(e) = self.try_enqueue(line) {
+                log::warn!("failed to stop generation for {id}: {e}");
+            }
+        }
+        cancelled || stop_generation
+    }
+
+    /// Hands a response to the request waiting on `id`, giving it back if
+    /// nobody is waiting for it.
+    pub(super) fn resolve(&self, id: &str, res: Value) -> Option<Value> {
+        let Some(Pending { kind, window, tx }) = lock(&self.pending).remove(id) else {
+            if id.starts_with(UNAWAITED) {
+                return None;
+            }
+            return Some(res);
+        };
+        // Recorded here on the reader thread, so the job's first events
+        // already know where to go
+        if kind == "get_gen_packets" {
+            if let Some(job_id) = res
+                .get("payload")
+                .and_then(|payload| payload.get("job_id"))
+                .and_then(Value::as_str)
+            {
+                *lock(&self.active_job) = Some(ActiveJob {
+                    id: job_id.to_string(),
+                    window,
+                });
+            }
+        }
+        tx.send(Ok(res)).err().and_then(Result::ok)
+    }
+
+    fn abandon(&self, id: &str, window: Option<String>) {
+        if let Some(window) = window {
+            lock(&self.abandoned).push(id.to_string(), window);
+        }
+    }
+
+    /// The window of an abandoned request whose reply arrived too late.
+    pub(super) fn abandoned_window(&self, id: &str) -> Option<String> {
+        let abandoned = lock(&self.abandoned);
+        abandoned
+            .0
+            .iter()
+            .rev()
+            .find(|(abandoned_id, _)| abandoned_id == id)
+            .map(|(_, window)| window.clone())
+    }
+}
+
+pub(super) fn into_result(res: Value) -> Result<Value, BridgeError> {
+    if res.get("status").and_then(Value::as_str) == Some("ok") {
+        Ok(res.get("payload").cloned().unwrap_or(Value::Null))
+    } else {
+        Err(BridgeError::Sidecar(
+            res.get("error")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+                .to_string(),
+        ))
+    }
+}
@@ -0,0 +1,201 @@
+//! Which request kinds each window may send, and what their bodies must hold.
+//!
+//! Requests from a window are checked here before they are queued, and so
+//! are calls to the HTTP API, under the label `http`; requests from the
+//! shell itself and the socket are trusted. The allowlist comes from
+//! `plugins.bridge.windows` in `tauri.conf.json`, mapping a window label (or
+//! a `prefix-*` pattern) to kinds, where `*` stands for every kind with a
+//! schema below. The shell commands that change the bridge or read what it
+//! recorded are allowed the same way.
+
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use super::{lock, Bridge, BridgeError, HTTP_CALLER};
+use crate::protocol::{
+    camelize, DbCreds, JobRef, LogsRead, PacketExport, PacketPage, PacketRef, SpecRef, SqlQuery,
+    TableRef, TableSpec,
+};
+
+type Check = fn(Value) -> Result<(), String>;
+
+// Every kind a window may ever send. Anything else, `hello` and `exit`
+// included, is the shell's business.
+const SCHEMAS: &[(&str, Check)] = &[
+    ("ping", no_body),
+    ("get_db_info", no_body),
+    ("get_db_last_connected", no_body),
+    ("set_db_connect", connect_creds),
+    ("set_db_reconnect", shape::<DbCreds>),
+    ("set_db_disconnect", no_body),
+    ("get_pref_connections", no_body),
+    ("set_pref_delete", shape::<DbCreds>),
+    ("get_db_tables", no_body),
+    ("get_db_table", shape::<TableRef>),
+    ("get_gen_methods", no_body),
+    ("get_gen_packets", shape::<TableSpec>),
+    ("get_gen_packet", shape::<PacketPage>),
+    ("poll_gen_status", shape::<JobRef>),
+    ("clear_gen_packets", no_body),
+    ("get_pref_spec", shape::<SpecRef>),
+    ("get_pref_rows", no_body),
+    ("get_sql_banner", no_body),
+    ("run_sql_query", shape::<SqlQuery>),
+    ("get_logs_read", optional::<LogsRead>),
+    ("set_logs_clear", no_body),
+    ("set_db_insert", shape::<PacketRef>),
+    ("set_db_export", shape::<PacketExport>),
+    ("set_db_commit", no_body),
+    ("set_db_rollback", no_body),
+    ("get_uncommitted_db", no_body),
+];
+
+// Shell commands a window may only call when its allowlist names them.
+const COMMANDS: &[&str] = &[
+    "cancel_request",
+    "set_request_timeouts",
+    "bridge_journal_start",
+    "bridge_journal_stop",
+    "audit_query",
+    "vault_status",
+    "vault_unlock",
+    "vault_lock",
+    "credential_sources",
+    "set_log_level",
+];
+
+fn no_body(_: Value) -> Result<(), String> {
+    Ok(())
+}
+
+fn shape<T: DeserializeOwned>(body: Value) -> Result<(), String> {
+    if !body.is_object() {
+        return Err("body must be an object".into());
+    }
+    // The populator accepts either case, so both are checked the same way
+    serde_json::from_value::<T>(camelize(body))
+        .map(drop)
+        .map_err(|e| e.to_string())
+}
+
+fn optional<T: DeserializeOwned>(body: Value) -> Result<(), String> {
+    match body {
+        Value::Null => Ok(()),
+        body => shape::<T>(body),
+    }
+}
+
+// Without a password the shell reads it from the connection's saved source
+fn connect_creds(body: Value) -> Result<(), String> {
+    let creds: DbCreds = serde_json::from_value(camelize(body)).map_err(|e| e.to_string())?;
+    match creds.password_source {
+        Some(_) => Err("password sources can only be set up from the app".into()),
+        None => Ok(()),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Policy {
+    windows: BTreeMap<String, Vec<String>>,
+}
+
+// Only the main window and the HTTP API exist unless configured otherwise.
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            windows: BTreeMap::from([
+                ("main".into(), vec!["*".into()]),
+                (HTTP_CALLER.into(), vec!["*".into()]),
+            ]),
+        }
+    }
+}
+
+impl Policy {
+    /// Reads the `plugins.bridge` section of the app config, falling back to
+    /// the default when it is missing or malformed.
+    pub fn from_config(config: Option<&Value>) -> Self {
+        let Some(config) = config else {
+            return Policy::default();
+        };
+        serde_json::from_value(config.clone()).unwrap_or_else(|e| {
+            log::warn!("invalid bridge policy, using the default: {e}");
+            Policy::default()
+        })
+    }
+
+    pub fn allow(mut self, window: impl Into<String>, kinds: &[&str]) -> Self {
+        self.windows.insert(
+            window.into(),
+            kinds.iter().map(|kind| kind.to_string()).collect(),
+        );
+        self
+    }
+
+    /// The kinds `window` may send: those of its exact label, else of the
+    /// longest pattern it matches.
+    fn kinds(&self, window: &str) -> &[String] {
+        if let Some(kinds) = self.windows.get(window) {
+            return kinds;
+        }
+        self.windows
+            .iter()
+            .filter_map(|(pattern, kinds)| {
+                let prefix = pattern.strip_suffix('*')?;
+                window.starts_with(prefix).then_some((prefix.len(), kinds))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map_or(&[], |(_, kinds)| kinds.as_slice())
+    }
+
+    fn allows(&self, window: &str, kind: &str) -> bool {
+        self.kinds(window).iter().any(|k| k == "*" || k == kind)
+    }
+
+    pub fn check(&self, window: &str, kind: &str, body: &Value) -> Result<(), BridgeError> {
+        let reject = |reason: String| BridgeError::Rejected {
+            kind: kind.into(),
+            reason,
+        };
+        let Some(&(_, check)) = SCHEMAS.iter().find(|(name, _)| *name == kind) else {
+            return Err(reject("unknown request kind".into()));
+        };
+        if !self.allows(window, kind) {
+            return Err(reject(format!("not allowed from window {window}")));
+        }
+        check(body.clone()).map_err(|e| reject(format!("invalid body: {e}")))
+    }
+
+    pub fn check_command(&self, window: &str, command: &str) -> Result<(), BridgeError> {
+        let reject = |reason: String| BridgeError::Rejected {
+            kind: command.into(),
+            reason,
+        };
+        if !COMMANDS.contains(&command) {
+            return Err(reject("unknown shell command".into()));
+        }
+        if !self.allows(window, command) {
+            return Err(reject(format!("not allowed from window {window}")));
+        }
+        Ok(())
+    }
+}
+
+impl Bridge {
+    pub fn set_policy(&self, policy: Policy) {
+        *lock(&self.policy) = policy;
+    }
+
+    /// Whether `window` may send this request; [`Bridge::request`] checks
+    /// again before sending it.
+    pub fn check_policy(&self, window: &str, kind: &str, body: &Value) -> Result<(), BridgeError> {
+        lock(&self.policy).check(window, kind, body)
+    }
+
+    /// Whether `window` may call the shell command `command`.
+    pub fn check_command(&self, window: &str, command: &str) -> Result<(), BridgeError> {
+        lock(&self.policy).check_command(window, command)
+    }
+}
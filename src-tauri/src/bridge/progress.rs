@@ -0,0 +1,176 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{lock, Bridge, EventSink};
+use crate::protocol::{camelize, GenDone, GenProgress, GenProgressEvent, TablePacket};
+
+pub const PROGRESS_EVENT: &str = "gen-progress";
+pub const DONE_EVENT: &str = "gen-done";
+
+// The populator pushes far more often than a progress bar needs repainting.
+const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+// Throughput is averaged over this many distinct row counts.
+const RATE_WINDOW: usize = 20;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressUpdate {
+    job_id: String,
+    #[serde(default)]
+    progress: GenProgress,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DoneUpdate {
+    job_id: String,
+    data: Option<TablePacket>,
+    error: Option<String>,
+}
+
+/// Coalesces the progress pushes of one generation job.
+pub(super) struct Tracker {
+    job_id: String,
+    samples: VecDeque<(Instant, u32)>,
+    last_emit: Option<Instant>,
+    last_status: String,
+}
+
+impl Tracker {
+    fn new(job_id: String) -> Self {
+        Tracker {
+            job_id,
+            samples: VecDeque::with_capacity(RATE_WINDOW),
+            last_emit: None,
+            last_status: String::new(),
+        }
+    }
+
+    /// Folds in an update, returning the event to emit if one is due.
+    fn update(&mut self, progress: GenProgress, now: Instant) -> Option<GenProgressEvent> {
+        if self
+            .samples
+            .back()
+            .map_or(true, |&(_, row)| row != progress.row)
+        {
+            if self.samples.len() == RATE_WINDOW {
+                self.samples.pop_front();
+            }
+            self.samples.push_back((now, progress.row));
+        }
+
+        // Status changes and the last row always go out, whatever the throttle says
+        let due = self
+            .last_emit
+            .map_or(true, |at| now.duration_since(at) >= EMIT_INTERVAL)
+            || progress.status != self.last_status
+            || progress.row >= progress.total;
+        if !due {
+            return None;
+        }
+        self.last_emit = Some(now);
+        self.last_status.clone_from(&progress.status);
+
+        let rows_per_sec = self.rate();
+        let eta_ms = rows_per_sec.map(|rate| {
+            (f64::from(progress.total.saturating_sub(progress.row)) / rate * 1000.0) as u64
+        });
+        Some(GenProgressEvent {
+            job_id: self.job_id.clone(),
+            status: progress.status,
+            row: progress.row,
+            total: progress.total,
+            column: progress.column,
+            rows_per_sec,
+            eta_ms,
+        })
+    }
+
+    fn rate(&self) -> Option<f64> {
+        let (&(first_at, first_row), &(last_at, last_row)) =
+            (self.samples.front()?, self.samples.back()?);
+        let secs = last_at.duration_since(first_at).as_secs_f64();
+        (secs > 0.0 && last_row > first_row).then(|| f64::from(last_row - first_row) / secs)
+    }
+}
+
+impl Bridge {
+    /// Handles an event the populator pushed on its own, returning false if
+    /// it isn't one we know or its body doesn't parse.
+    pub(super) fn on_event(&self, events: &dyn EventSink, event: &str, body: Value) -> bool {
+        match event {
+            PROGRESS_EVENT => {
+                let Ok(update) = serde_json::from_value::<ProgressUpdate>(camelize(body)) else {
+                    return false;
+                };
+                let mut tracker = lock(&self.progress);
+                if !tracker.as_ref().is_some_and(|t| t.job_id == update.job_id) {
+                    *tracker = Some(Tracker::new(update.job_id));
+                }
+                let now = Instant::now();
+                if let Some(progress) = tracker
+                    .as_mut()
+                    .and_then(|t| t.update(update.progress, now))
+                {
+                    match self.job_window(&progress.job_id) {
+                        Some(window) => events.emit_to(window.as_deref(), PROGRESS_EVENT, progress),
+                        None => log::debug!("dropping progress of unknown job {}", progress.job_id),
+                    }
+                }
+                true
+            }
+            DONE_EVENT => {
+                let Ok(done) = serde_json::from_value::<DoneUpdate>(camelize(body)) else {
+                    return false;
+                };
+                let mut tracker = lock(&self.progress);
+                if tracker.as_ref().is_some_and(|t| t.job_id == done.job_id) {
+                    tracker.take();
+                }
+                if let Some(packet) = &done.data {
+                    self.audit.note_packet(packet);
+                }
+                let window = {
+                    let mut active_job = lock(&self.active_job);
+                    match active_job.take() {
+                        Some(job) if job.id == done.job_id => Some(job.window),
+                        job => {
+                            *active_job = job;
+                            None
+                        }
+                    }
+                };
+                // Its data may only reach whoever started the job
+                let Some(window) = window else {
+                    log::warn!("dropping the result of unknown job {}", done.job_id);
+                    return true;
+                };
+                events.emit_to(
+                    window.as_deref(),
+                    DONE_EVENT,
+                    GenDone {
+                        job_id: done.job_id,
+                        data: done.data,
+                        error: done.error,
+                    },
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The window that started `job_id`, or `Some(None)` if a caller
+    /// without one did; `None` if it isn't the running job.
+    fn job_window(&self, job_id: &str) -> Option<Option<String>> {
+        lock(&self.active_job)
+            .as_ref()
+            .filter(|job| job.id == job_id)
+            .map(|job| job.window.clone())
+    }
+}
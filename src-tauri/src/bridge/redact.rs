@@ -0,0 +1,122 @@
+//! Scrubbing secrets out of populator traffic before it is written anywhere.
+
+use serde_json::Value;
+
+use super::journal::Direction;
+
+const REDACTED: &str = "***";
+// Generated rows can make a single message megabytes long
+const MAX_LOGGED: usize = 4096;
+
+/// Blanks every value whose key mentions a password, at any depth.
+pub(super) fn redact_passwords(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key.to_ascii_lowercase().contains("password") {
+                    if !value.is_null() {
+                        *value = Value::from(REDACTED);
+                    }
+                } else {
+                    redact_passwords(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_passwords),
+        _ => {}
+    }
+}
+
+/// Replaces the string and number literals of `sql` with `?`, keeping its
+/// shape readable.
+pub fn redact_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    // Whether the previous char could be part of an identifier like `t1`
+    let mut in_word = false;
+    while let Some(c) = chars.next() {
+        match c {
+            // MySQL quotes strings either way
+            '\'' | '"' => {
+                let quote = c;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        // A doubled quote is an escaped one
+                        c if c == quote && chars.peek() == Some(&quote) => {
+                            chars.next();
+                        }
+                        c if c == quote => break,
+                        _ => {}
+                    }
+                }
+                out.push('?');
+                in_word = false;
+            }
+            c if c.is_ascii_digit() && !in_word => {
+                while chars
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '.')
+                {
+                    chars.next();
+                }
+                out.push('?');
+                in_word = false;
+            }
+            c => {
+                out.push(c);
+                in_word = c.is_alphanumeric() || c == '_' || c == '$';
+            }
+        }
+    }
+    out
+}
+
+/// Redacts SQL literals in every string under a `sql` key, at any depth.
+fn redact_statements(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(sql) if key.eq_ignore_ascii_case("sql") => {
+                        *sql = redact_sql(sql);
+                    }
+                    value => redact_statements(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_statements),
+        _ => {}
+    }
+}
+
+/// Logs one message exchanged with the populator at debug level, without
+/// passwords or SQL literals.
+pub(super) fn log_traffic(dir: Direction, line: &str) {
+    if !log::log_enabled!(target: "bridge::traffic", log::Level::Debug) {
+        return;
+    }
+    let line = match serde_json::from_str::<Value>(line) {
+        Ok(mut msg) => {
+            redact_passwords(&mut msg);
+            redact_statements(&mut msg);
+            msg.to_string()
+        }
+        Err(_) => line.to_string(),
+    };
+    let arrow = match dir {
+        Direction::Out => "->",
+        Direction::In => "<-",
+    };
+    match line.char_indices().nth(MAX_LOGGED) {
+        Some((cut, _)) => log::debug!(
+            target: "bridge::traffic",
+            "{arrow} {}... ({} bytes)",
+            &line[..cut],
+            line.len()
+        ),
+        None => log::debug!(target: "bridge::traffic", "{arrow} {line}"),
+    }
+}
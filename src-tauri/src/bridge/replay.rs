@@ -0,0 +1,146 @@
+//! A fake populator that answers from a recorded journal.
+//!
+//! Each request is matched to the next unused recorded request of the same
+//! kind; bodies are not compared. The replies and events that followed it in
+//! the recording are written back in order, with the reply id swapped for the
+//! live one. A request whose recorded reply never came is left unanswered, so
+//! hangs reproduce as hangs.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+};
+
+use serde_json::{json, Value};
+
+use super::{
+    framing::{read_messages, Framing},
+    handshake::SUPPORTED_PROTOCOLS,
+    journal::{Direction, Entry},
+};
+
+/// Runs the current executable as a replaying sidecar instead of the app.
+pub const REPLAY_FLAG: &str = "--replay-sidecar";
+/// Journal the app should replay instead of spawning the populator.
+pub const REPLAY_ENV: &str = "DATASMITH_REPLAY";
+
+struct Exchange {
+    kind: String,
+    id: Option<String>,
+    replies: Vec<Value>,
+}
+
+fn kind_of(msg: &Value) -> String {
+    match msg {
+        Value::String(line) => line.trim().to_string(),
+        msg => msg
+            .get("kind")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+fn id_of(msg: &Value) -> Option<String> {
+    msg.get("id").and_then(Value::as_str).map(String::from)
+}
+
+fn load(path: &Path) -> io::Result<Vec<Exchange>> {
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line)?;
+        match entry.dir {
+            Direction::Out => exchanges.push(Exchange {
+                kind: kind_of(&entry.msg),
+                id: id_of(&entry.msg),
+                replies: Vec::new(),
+            }),
+            // Replies go to the request they answer; events and id-less
+            // acknowledgements to whatever was sent last
+            Direction::In => {
+                let target = id_of(&entry.msg)
+                    .and_then(|id| {
+                        exchanges
+                            .iter()
+                            .rposition(|e| e.id.as_deref() == Some(id.as_str()))
+                    })
+                    .or(exchanges.len().checked_sub(1));
+                if let Some(i) = target {
+                    exchanges[i].replies.push(entry.msg);
+                }
+            }
+        }
+    }
+    Ok(exchanges)
+}
+
+// Keeps the app usable when the recording started after the handshake or
+// doesn't cover a request at all.
+fn fallback(kind: &str, id: Option<&str>) -> Value {
+    match kind {
+        "hello" => json!({
+            "id": id,
+            "status": "ok",
+            "payload": {
+                "protocol": SUPPORTED_PROTOCOLS.end(),
+                "version": "replay",
+                "kinds": [],
+                "framing": Framing::Line,
+            },
+        }),
+        "exit" => json!({ "status": "ok", "payload": "exiting..." }),
+        kind => json!({
+            "id": id,
+            "status": "error",
+            "error": format!("no recorded reply for {kind}"),
+        }),
+    }
+}
+
+/// Serves `journal` over stdin/stdout until stdin closes or `exit` arrives.
+pub fn serve_replay(journal: &Path) -> io::Result<()> {
+    let mut exchanges = load(journal)?;
+    let mut framing = Framing::Line;
+    let mut stdout = io::stdout().lock();
+
+    for message in read_messages(BufReader::new(io::stdin())) {
+        if message.trim().is_empty() {
+            continue;
+        }
+        let msg = serde_json::from_str(&message).unwrap_or_else(|_| Value::from(message));
+        let kind = kind_of(&msg);
+        let id = id_of(&msg);
+
+        let replies = match exchanges.iter().position(|e| e.kind == kind) {
+            Some(i) => {
+                let exchange = exchanges.remove(i);
+                let mut replies = exchange.replies;
+                for reply in &mut replies {
+                    if exchange.id.is_some() && id_of(reply) == exchange.id {
+                        reply["id"] = Value::from(id.clone());
+                    }
+                }
+                replies
+            }
+            None => vec![fallback(&kind, id.as_deref())],
+        };
+
+        for reply in replies {
+            stdout.write_all(&framing.encode(&reply.to_string()))?;
+            stdout.flush()?;
+            if kind == "hello" && id_of(&reply) == id {
+                framing =
+                    serde_json::from_value(reply["payload"]["framing"].clone()).unwrap_or_default();
+            }
+        }
+        if kind == "exit" {
+            break;
+        }
+    }
+    Ok(())
+}
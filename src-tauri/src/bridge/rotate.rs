@@ -0,0 +1,75 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Append-only log file that rolls over to `<name>.1`, `<name>.2`, ... once it
+/// grows past `max_bytes`, keeping at most `keep` old files around.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFile {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> Self {
+        RotatingFile {
+            path,
+            max_bytes,
+            keep,
+            file: None,
+            written: 0,
+        }
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.file.is_some() && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                if let Some(dir) = self.path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                self.written = file.metadata()?.len();
+                self.file.insert(file)
+            }
+        };
+        writeln!(file, "{line}")?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        for i in (1..self.keep).rev() {
+            let from = self.rotated(i);
+            if from.exists() {
+                fs::rename(from, self.rotated(i + 1))?;
+            }
+        }
+        if self.keep > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+
+    fn rotated(&self, i: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{i}"));
+        PathBuf::from(name)
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    process::Command,
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use super::{Bridge, Shared};
+
+/// The window whose closing quits the app.
+pub const MAIN_WINDOW: &str = "main";
+
+const EXIT_TIMEOUT: Duration = Duration::from_secs(3);
+const UNCOMMITTED_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl Bridge {
+    /// Asks the populator to exit, waits up to `timeout` for it to do so and
+    /// then kills whatever is left of its process tree.
+    pub fn shutdown(&self, timeout: Duration) {
+        if self.shutdown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        #[cfg(unix)]
+        self.remove_socket();
+        self.remove_http_info();
+        let Some(pid) = self.pid() else {
+            return;
+        };
+
+        if let Err(e) = self.try_enqueue("exit".into()) {
+            log::warn!("failed to ask populator to exit: {e}");
+        }
+
+        let deadline = Instant::now() + timeout;
+        while self.pid().is_some() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        if self.pid().is_some() {
+            log::warn!("populator did not exit within {timeout:?}, killing it");
+        }
+        kill_tree(pid);
+    }
+}
+
+/// Handles a close request on the main window: warns about uncommitted writes,
+/// then shuts the sidecar down before letting the app exit.
+pub fn confirm_close(app: &AppHandle) {
+    let bridge = app.state::<Shared>().inner().clone();
+    if bridge.closing.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let pending = bridge
+            .call("get_uncommitted_db", Value::Null, UNCOMMITTED_TIMEOUT)
+            .ok()
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+
+        if pending > 0 {
+            let quit = app
+                .dialog()
+                .message(format!(
+                    "{pending} inserted row(s) have not been committed and will be rolled back if you quit."
+                ))
+                .title("Uncommitted changes")
+                .kind(MessageDialogKind::Warning)
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Quit anyway".into(),
+                    "Cancel".into(),
+                ))
+                .blocking_show();
+            if !quit {
+                bridge.closing.store(false, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        bridge.shutdown(EXIT_TIMEOUT);
+        app.exit(0);
+    });
+}
+
+/// Exit path for when the app is going away without a window close request.
+pub fn shutdown_on_exit(app: &AppHandle) {
+    app.state::<Shared>().shutdown(EXIT_TIMEOUT);
+}
+
+#[cfg(unix)]
+pub(super) fn kill_tree(pid: u32) {
+    // The child leads its own process group, see `spawn_supervisor`
+    Command::new("kill")
+        .args(["-KILL", "--", &format!("-{pid}")])
+        .status()
+        .ok();
+}
+
+#[cfg(windows)]
+pub(super) fn kill_tree(pid: u32) {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .ok();
+}
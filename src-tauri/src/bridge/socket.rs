@@ -0,0 +1,130 @@
+//! Local socket that lets scripts drive the running app.
+//!
+//! Clients write `{id, kind, body}` lines and get the populator's
+//! `{id, status, payload | error}` lines back, under their own id. Each request
+//! goes through [`Bridge::request`], so it is multiplexed with the UI's
+//! traffic and replies may come back out of order. Only the owning user can
+//! reach the socket: its directory is 0700 and the socket itself 0600.
+
+use std::{
+    fs::{self, DirBuilder},
+    io::{self, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{framing::read_lines, lock, Bridge, RequestOptions, Shared};
+
+/// Listens on the socket at startup when set.
+pub const SOCKET_ENV: &str = "DATASMITH_SOCKET";
+
+#[derive(Deserialize)]
+struct ClientRequest {
+    #[serde(default)]
+    id: Value,
+    kind: String,
+    #[serde(default)]
+    body: Value,
+}
+
+impl Bridge {
+    /// Starts accepting clients on `path`, replacing a stale socket left
+    /// behind by a crashed instance but not a live one.
+    pub fn serve_socket(self: &Arc<Self>, path: PathBuf) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} is in use by another instance", path.display()),
+                ));
+            }
+            fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        *lock(&self.socket) = Some(path);
+
+        let bridge = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let bridge = bridge.clone();
+                        thread::spawn(move || serve_client(bridge, stream));
+                    }
+                    Err(e) => log::warn!("failed to accept socket client: {e}"),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub(super) fn remove_socket(&self) {
+        if let Some(path) = lock(&self.socket).take() {
+            fs::remove_file(&path).ok();
+        }
+    }
+}
+
+fn serve_client(bridge: Shared, stream: UnixStream) {
+    let reader = match stream.try_clone() {
+        Ok(reader) => BufReader::new(reader),
+        Err(e) => {
+            log::warn!("failed to read socket client: {e}");
+            return;
+        }
+    };
+    let writer = Arc::new(Mutex::new(stream));
+
+    for line in read_lines(reader) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match serde_json::from_str::<ClientRequest>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = format!("invalid request: {e}");
+                reply_to(
+                    &writer,
+                    json!({ "id": null, "status": "error", "error": error }),
+                );
+                continue;
+            }
+        };
+
+        let bridge = bridge.clone();
+        let writer = writer.clone();
+        tauri::async_runtime::spawn(async move {
+            let id = request.id;
+            let reply = match bridge
+                .request(&request.kind, request.body, RequestOptions::default())
+                .await
+            {
+                Ok(payload) => json!({ "id": id, "status": "ok", "payload": payload }),
+                Err(e) => json!({ "id": id, "status": "error", "error": e.to_string() }),
+            };
+            reply_to(&writer, reply);
+        });
+    }
+}
+
+fn reply_to(writer: &Mutex<UnixStream>, reply: Value) {
+    let mut stream = lock(writer);
+    // A client that hung up early just doesn't get its reply
+    writeln!(stream, "{reply}")
+        .and_then(|_| stream.flush())
+        .ok();
+}
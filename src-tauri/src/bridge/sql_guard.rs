@@ -0,0 +1,255 @@
+//! Classifies console SQL before it runs and asks before destructive
+//! statements, since the populator commits whatever it is given.
+
+use std::cmp::Ordering;
+
+use serde_json::Value;
+use sqlparser::{
+    ast::{FromTable, Query, SetExpr, Statement},
+    dialect::{Dialect, GenericDialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect},
+    parser::Parser,
+};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::oneshot;
+
+use super::{BridgeError, Shared};
+use crate::protocol::DbDialect;
+
+/// How much damage a statement can do, from least to most.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SqlRisk {
+    ReadOnly,
+    /// Inserts, and updates or deletes limited by a `WHERE`.
+    Write,
+    /// Parsed, but not a statement we know to be harmless.
+    Unknown,
+    /// An `UPDATE` or `DELETE` without a `WHERE`.
+    UnboundedWrite,
+    Ddl,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlVerdict {
+    /// The riskiest of the statements.
+    pub risk: SqlRisk,
+    /// What the risky statements touch, e.g. `TABLE orders`.
+    pub objects: Vec<String>,
+    /// Why the SQL could not be parsed, in which case the risk is unknown.
+    pub error: Option<String>,
+}
+
+impl SqlVerdict {
+    pub fn needs_confirmation(&self) -> bool {
+        self.risk >= SqlRisk::Unknown
+    }
+
+    fn describe(&self) -> String {
+        let objects = self.objects.join(", ");
+        let what = match (self.risk, &self.error) {
+            (SqlRisk::Ddl, _) => format!("This changes the database schema: {objects}."),
+            (SqlRisk::UnboundedWrite, _) => {
+                format!("This has no WHERE clause and changes every row of: {objects}.")
+            }
+            (_, Some(e)) => format!("This SQL could not be checked before running: {e}"),
+            _ => "This statement could not be classified as safe.".into(),
+        };
+        format!("{what}\n\nIt is committed as soon as it runs. Run it anyway?")
+    }
+}
+
+pub fn classify_sql(sql: &str, dialect: DbDialect) -> SqlVerdict {
+    let parser_dialect: Box<dyn Dialect> = match dialect {
+        DbDialect::Mysql | DbDialect::Mariadb => Box::new(MySqlDialect {}),
+        DbDialect::Postgresql => Box::new(PostgreSqlDialect {}),
+        DbDialect::Mssql => Box::new(MsSqlDialect {}),
+        _ => Box::new(GenericDialect {}),
+    };
+    let statements = match Parser::parse_sql(&*parser_dialect, sql) {
+        Ok(statements) => statements,
+        Err(e) => {
+            return SqlVerdict {
+                risk: SqlRisk::Unknown,
+                objects: Vec::new(),
+                error: Some(e.to_string()),
+            }
+        }
+    };
+
+    let (risk, objects) = statements
+        .iter()
+        .map(classify)
+        .fold((SqlRisk::ReadOnly, Vec::new()), riskiest);
+    SqlVerdict {
+        risk,
+        objects,
+        error: None,
+    }
+}
+
+/// The riskier of two classifications, with the objects of both when they
+/// are as risky.
+fn riskiest(a: (SqlRisk, Vec<String>), b: (SqlRisk, Vec<String>)) -> (SqlRisk, Vec<String>) {
+    match a.0.cmp(&b.0) {
+        Ordering::Greater => a,
+        Ordering::Less => b,
+        Ordering::Equal => {
+            let (risk, mut objects) = a;
+            for object in b.1 {
+                if !objects.contains(&object) {
+                    objects.push(object);
+                }
+            }
+            (risk, objects)
+        }
+    }
+}
+
+fn classify(statement: &Statement) -> (SqlRisk, Vec<String>) {
+    match statement {
+        // EXPLAIN ANALYZE runs the statement
+        Statement::Explain {
+            analyze: true,
+            statement,
+            ..
+        } => classify(statement),
+        Statement::Query(query) => classify_query(query),
+        Statement::Explain { .. }
+        | Statement::ExplainTable { .. }
+        | Statement::ShowTables { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::ShowCreate { .. }
+        | Statement::ShowVariable { .. }
+        | Statement::ShowVariables { .. }
+        | Statement::Use { .. } => (SqlRisk::ReadOnly, Vec::new()),
+        Statement::Insert { .. } => (SqlRisk::Write, Vec::new()),
+        Statement::Update {
+            table, selection, ..
+        } => match selection {
+            Some(_) => (SqlRisk::Write, Vec::new()),
+            None => (
+                SqlRisk::UnboundedWrite,
+                vec![format!("TABLE {}", table.relation)],
+            ),
+        },
+        Statement::Delete(delete) => {
+            if delete.selection.is_some() {
+                return (SqlRisk::Write, Vec::new());
+            }
+            let (FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from)) = &delete.from;
+            let tables = match delete.tables.is_empty() {
+                true => from.iter().map(|t| t.relation.to_string()).collect(),
+                false => delete.tables.iter().map(ToString::to_string).collect(),
+            };
+            (SqlRisk::UnboundedWrite, tables_of(tables))
+        }
+        Statement::Drop {
+            object_type, names, ..
+        } => (
+            SqlRisk::Ddl,
+            names
+                .iter()
+                .map(|name| format!("{object_type} {name}"))
+                .collect(),
+        ),
+        Statement::Truncate { table_names, .. } => (
+            SqlRisk::Ddl,
+            tables_of(table_names.iter().map(|t| t.name.to_string()).collect()),
+        ),
+        Statement::AlterTable { name, .. } => (SqlRisk::Ddl, vec![format!("TABLE {name}")]),
+        Statement::CreateTable(create) => (SqlRisk::Ddl, vec![format!("TABLE {}", create.name)]),
+        _ => (SqlRisk::Unknown, Vec::new()),
+    }
+}
+
+/// Queries only read, unless a CTE changes data or they select into a new
+/// table.
+fn classify_query(query: &Query) -> (SqlRisk, Vec<String>) {
+    query
+        .with
+        .iter()
+        .flat_map(|with| &with.cte_tables)
+        .map(|cte| classify_query(&cte.query))
+        .chain([classify_set_expr(&query.body)])
+        .fold((SqlRisk::ReadOnly, Vec::new()), riskiest)
+}
+
+fn classify_set_expr(body: &SetExpr) -> (SqlRisk, Vec<String>) {
+    match body {
+        SetExpr::Select(select) => match &select.into {
+            Some(into) => (SqlRisk::Ddl, vec![format!("TABLE {}", into.name)]),
+            None => (SqlRisk::ReadOnly, Vec::new()),
+        },
+        SetExpr::Query(query) => classify_query(query),
+        SetExpr::SetOperation { left, right, .. } => {
+            riskiest(classify_set_expr(left), classify_set_expr(right))
+        }
+        SetExpr::Insert(statement) | SetExpr::Update(statement) => classify(statement),
+        SetExpr::Values(_) | SetExpr::Table(_) => (SqlRisk::ReadOnly, Vec::new()),
+    }
+}
+
+fn tables_of(names: Vec<String>) -> Vec<String> {
+    names
+        .into_iter()
+        .map(|name| format!("TABLE {name}"))
+        .collect()
+}
+
+/// Asks in `window` before running the `run_sql_query` in `body`, if the
+/// window may send it at all. One it may not send is left for
+/// [`Bridge::request`](super::Bridge::request) to reject and audit, without
+/// a dialog.
+pub async fn confirm_sql_request(
+    app: &AppHandle,
+    window: &str,
+    body: &Value,
+) -> Result<(), BridgeError> {
+    let allowed = app
+        .state::<Shared>()
+        .check_policy(window, "run_sql_query", body)
+        .is_ok();
+    if !allowed {
+        return Ok(());
+    }
+    let sql = body.get("sql").and_then(Value::as_str).unwrap_or_default();
+    confirm_sql(app, window, sql).await
+}
+
+/// Asks in `window` before running destructive `sql`, failing with
+/// [`BridgeError::Cancelled`] if the user declines.
+async fn confirm_sql(app: &AppHandle, window: &str, sql: &str) -> Result<(), BridgeError> {
+    let dialect = app
+        .state::<Shared>()
+        .connection()
+        .map(|connection| connection.dialect)
+        .unwrap_or_default();
+    let verdict = classify_sql(sql, dialect);
+    if !verdict.needs_confirmation() {
+        return Ok(());
+    }
+
+    let (tx, rx) = oneshot::channel();
+    let mut dialog = app
+        .dialog()
+        .message(verdict.describe())
+        .title("Run destructive SQL?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Run".into(),
+            "Cancel".into(),
+        ));
+    if let Some(parent) = app.get_webview_window(window) {
+        dialog = dialog.parent(&parent);
+    }
+    dialog.show(move |run| {
+        tx.send(run).ok();
+    });
+
+    match rx.await {
+        Ok(true) => Ok(()),
+        _ => Err(BridgeError::Cancelled {
+            kind: "run_sql_query".into(),
+        }),
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    collections::VecDeque,
+    io::BufReader,
+    path::PathBuf,
+    process::ChildStderr,
+    sync::Mutex,
+    thread::{self, JoinHandle},
+};
+
+use super::{
+    framing::read_lines,
+    lock,
+    logstream::{python_level, LogSource},
+    rotate::RotatingFile,
+    Shared,
+};
+
+const RING_CAPACITY: usize = 1000;
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const KEEP_LOGS: usize = 3;
+
+/// Everything the populator writes to stderr, kept both in a rotating file
+/// and in a bounded in-memory buffer for the UI.
+pub struct StderrLog {
+    ring: Mutex<VecDeque<String>>,
+    file: Mutex<RotatingFile>,
+}
+
+impl StderrLog {
+    pub fn new(log_dir: PathBuf) -> Self {
+        StderrLog {
+            ring: Mutex::new(VecDeque::with_capacity(RING_CAPACITY)),
+            file: Mutex::new(RotatingFile::new(
+                log_dir.join("populator.stderr.log"),
+                MAX_LOG_BYTES,
+                KEEP_LOGS,
+            )),
+        }
+    }
+
+    pub fn push(&self, line: String) {
+        if let Err(e) = lock(&self.file).write_line(&line) {
+            log::warn!("failed to write populator stderr log: {e}");
+        }
+
+        let mut ring = lock(&self.ring);
+        if ring.len() == RING_CAPACITY {
+            ring.pop_front();
+        }
+        ring.push_back(line);
+    }
+
+    /// Returns the last `lines` lines, oldest first.
+    pub fn tail(&self, lines: usize) -> Vec<String> {
+        let ring = lock(&self.ring);
+        ring.iter()
+            .skip(ring.len().saturating_sub(lines))
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn spawn_stderr_reader(bridge: Shared, stderr: ChildStderr) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for line in read_lines(BufReader::new(stderr)) {
+            let level = python_level(&line, "WARN");
+            bridge
+                .logs
+                .push(LogSource::Stderr, level, "populator", line.clone());
+            bridge.stderr.push(line);
+        }
+    })
+}
@@ -0,0 +1,229 @@
+use std::{
+    cmp, env,
+    ffi::OsString,
+    io::{self, BufReader},
+    path::PathBuf,
+    process::{ChildStdout, Command, Stdio},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::{
+    diagnostics::DiagnosticKind,
+    framing::read_messages,
+    handshake::HandshakeError,
+    journal::Direction,
+    redact::log_traffic,
+    replay::{REPLAY_ENV, REPLAY_FLAG},
+    shutdown::kill_tree,
+    stderr::spawn_stderr_reader,
+    BridgeState, BridgeStatus, EventSink, Shared,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// A child that survived this long is considered healthy again and resets the backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// The child process the supervisor keeps alive: the populator in the app,
+/// anything that speaks its protocol elsewhere.
+#[derive(Clone, Debug)]
+pub struct Sidecar {
+    program: PathBuf,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+}
+
+impl Sidecar {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Sidecar {
+            program: program.into(),
+            args: Vec::new(),
+            envs: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // Own process group so shutdown can take the multiprocessing workers down too
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        command
+    }
+}
+
+/// The populator bundled next to the current executable, or this executable
+/// replaying a journal when [`REPLAY_ENV`] is set.
+pub fn populator_sidecar() -> io::Result<Sidecar> {
+    let current = env::current_exe()?;
+    if let Some(journal) = env::var_os(REPLAY_ENV) {
+        return Ok(Sidecar::new(current).arg(REPLAY_FLAG).arg(journal));
+    }
+    let dir = current.parent().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "executable has no parent directory",
+        )
+    })?;
+    Ok(Sidecar::new(dir.join(if cfg!(target_os = "windows") {
+        "populator.exe"
+    } else {
+        "populator"
+    })))
+}
+
+/// Spawns the sidecar and keeps it alive, respawning it with exponential
+/// backoff whenever it exits until the bridge is shut down.
+pub fn spawn_supervisor(
+    events: Arc<dyn EventSink>,
+    sidecar: Sidecar,
+    bridge: Shared,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut restarts = 0;
+        let mut backoff = INITIAL_BACKOFF;
+
+        while !bridge.is_shutting_down() {
+            bridge.set_status(
+                &*events,
+                status(BridgeState::Starting, restarts, None, None),
+            );
+
+            let mut command = sidecar.command();
+            let started = Instant::now();
+            let mut incompatible = None;
+            let (code, message) = match command.spawn() {
+                Ok(mut child) => {
+                    let stdout = child.stdout.take();
+                    let stderr = child.stderr.take();
+                    let writer = bridge.attach(child.stdin.take(), child.id());
+                    let reader =
+                        stdout.map(|stdout| spawn_reader(events.clone(), bridge.clone(), stdout));
+                    let stderr_reader =
+                        stderr.map(|stderr| spawn_stderr_reader(bridge.clone(), stderr));
+                    match bridge.hello() {
+                        Ok(handshake) => {
+                            log::info!(
+                                "populator {} ready (protocol {}, {:?} framing)",
+                                handshake.version,
+                                handshake.protocol,
+                                handshake.framing
+                            );
+                            bridge.set_status(
+                                &*events,
+                                status(BridgeState::Ready, restarts, None, None),
+                            );
+                        }
+                        Err(HandshakeError::Incompatible(reason)) => {
+                            incompatible = Some(reason);
+                            kill_tree(child.id());
+                        }
+                        Err(HandshakeError::Failed(e)) => {
+                            log::warn!("populator handshake failed: {e}");
+                            kill_tree(child.id());
+                        }
+                    }
+
+                    let exit = child.wait();
+                    bridge.detach();
+                    // Orphaned workers would otherwise keep the pipes open
+                    kill_tree(child.id());
+                    for handle in [writer, reader, stderr_reader].into_iter().flatten() {
+                        handle.join().ok();
+                    }
+                    match exit {
+                        Ok(exit) => match bridge.stderr_tail(1).pop() {
+                            Some(last) => {
+                                (exit.code(), format!("populator exited: {exit}: {last}"))
+                            }
+                            None => (exit.code(), format!("populator exited: {exit}")),
+                        },
+                        Err(e) => (None, format!("failed to wait on populator: {e}")),
+                    }
+                }
+                Err(e) => (None, format!("failed to spawn populator: {e}")),
+            };
+
+            if bridge.is_shutting_down() {
+                break;
+            }
+            // Restarting the same binary won't make it compatible
+            if let Some(reason) = incompatible {
+                bridge.set_status(
+                    &*events,
+                    status(BridgeState::Incompatible, restarts, code, Some(reason)),
+                );
+                break;
+            }
+            bridge.set_status(
+                &*events,
+                status(BridgeState::Crashed, restarts, code, Some(message)),
+            );
+
+            if started.elapsed() >= STABLE_AFTER {
+                backoff = INITIAL_BACKOFF;
+            }
+            restarts += 1;
+            bridge.set_status(
+                &*events,
+                status(BridgeState::Restarting, restarts, None, None),
+            );
+            thread::sleep(backoff);
+            backoff = cmp::min(backoff * 2, MAX_BACKOFF);
+        }
+    })
+}
+
+fn spawn_reader(events: Arc<dyn EventSink>, bridge: Shared, stdout: ChildStdout) -> JoinHandle<()> {
+    // Complete the pending request for every response line from Python
+    thread::spawn(move || {
+        for line in read_messages(BufReader::new(stdout)) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            bridge.journal.record(Direction::In, &line);
+            log_traffic(Direction::In, &line);
+            if let Err(diagnostic) = bridge.route(&*events, line) {
+                // The acknowledgement of `exit` carries no id
+                if bridge.is_shutting_down() && diagnostic.kind == DiagnosticKind::MissingId {
+                    continue;
+                }
+                diagnostic.report(&*events);
+            }
+        }
+    })
+}
+
+fn status(
+    state: BridgeState,
+    restarts: u32,
+    code: Option<i32>,
+    message: Option<String>,
+) -> BridgeStatus {
+    BridgeStatus {
+        state,
+        restarts,
+        code,
+        message,
+    }
+}
@@ -0,0 +1,45 @@
+use std::{collections::HashMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Kinds that legitimately take longer than the default. `run_sql_query` gives
+// up on its own after 20 seconds, so it keeps the default.
+const KIND_TIMEOUTS: &[(&str, u64)] = &[
+    ("get_db_last_connected", 60),
+    ("set_db_connect", 60),
+    ("set_db_reconnect", 60),
+    ("get_db_tables", 120),
+    ("get_db_table", 60),
+    ("set_db_insert", 300),
+    ("set_db_export", 300),
+    ("set_db_commit", 120),
+    ("set_db_rollback", 120),
+];
+
+/// Deadlines applied to bridge requests that don't bring their own.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeouts {
+    pub default_ms: u64,
+    pub kinds: HashMap<String, u64>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            default_ms: DEFAULT_TIMEOUT.as_millis() as u64,
+            kinds: KIND_TIMEOUTS
+                .iter()
+                .map(|(kind, secs)| (kind.to_string(), secs * 1000))
+                .collect(),
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn for_kind(&self, kind: &str) -> Duration {
+        Duration::from_millis(*self.kinds.get(kind).unwrap_or(&self.default_ms))
+    }
+}
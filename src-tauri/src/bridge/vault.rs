@@ -0,0 +1,523 @@
+//! Connection passwords, encrypted at rest and held by the shell.
+//!
+//! The vault is a single file sealed with XChaCha20-Poly1305 under a key
+//! derived from a passphrase with Argon2id. Once it exists, passwords stay
+//! out of the populator's registry: connects are refused while the vault is
+//! locked, and saved passwords are filled into reconnects as they are sent.
+//! Passwords the registry still holds from before are taken over when the
+//! vault is unlocked or their connection is reopened, and the registry is
+//! only asked to forget each one once the vault file holds it. The key is
+//! dropped when the vault is locked, by hand or after sitting idle.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{lock, Bridge, BridgeError, RequestOptions};
+use crate::protocol::{camelize, DbCreds};
+
+const VERSION: u32 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+pub const DEFAULT_LOCK_AFTER: Duration = Duration::from_secs(15 * 60);
+
+type Key = Zeroizing<[u8; KEY_LEN]>;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub exists: bool,
+    pub unlocked: bool,
+    /// Idle time before the vault locks itself, while it is unlocked.
+    pub lock_after_secs: Option<u64>,
+}
+
+/// The vault file as stored on disk.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sealed {
+    version: u32,
+    kdf: Kdf,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Kdf {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+impl Kdf {
+    fn fresh() -> Result<Self, BridgeError> {
+        Ok(Kdf {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+            salt: hex(&random::<SALT_LEN>()?),
+        })
+    }
+
+    fn derive(&self, passphrase: &str) -> Result<Key, BridgeError> {
+        let corrupt = |e: String| BridgeError::Io(format!("vault key parameters are invalid: {e}"));
+        let salt = unhex(&self.salt).ok_or_else(|| corrupt("salt is not hex".into()))?;
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| corrupt(e.to_string()))?;
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut *key)
+            .map_err(|e| corrupt(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+struct Unlocked {
+    key: Key,
+    kdf: Kdf,
+    /// Passwords by connection identity, `user@host:port/name`.
+    passwords: BTreeMap<String, String>,
+    lock_after: Duration,
+    last_used: Instant,
+    generation: u64,
+}
+
+impl Drop for Unlocked {
+    fn drop(&mut self) {
+        self.passwords.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+/// What [`Vault::settle`] needs to know about a request in flight.
+pub(super) struct Ticket {
+    identity: String,
+    /// The password the request carried, to keep once it has worked.
+    password: Option<Zeroizing<String>>,
+    /// The connection, without its password.
+    creds: DbCreds,
+}
+
+#[derive(Default)]
+pub struct Vault {
+    path: Mutex<Option<PathBuf>>,
+    open: Mutex<Option<Unlocked>>,
+    generations: AtomicU64,
+}
+
+impl Vault {
+    fn exists(&self) -> bool {
+        lock(&self.path).as_ref().is_some_and(|path| path.exists())
+    }
+
+    fn status(&self) -> VaultStatus {
+        let open = lock(&self.open);
+        VaultStatus {
+            exists: self.exists(),
+            unlocked: open.is_some(),
+            lock_after_secs: open.as_ref().map(|open| open.lock_after.as_secs()),
+        }
+    }
+
+    /// Opens the vault, creating it under `passphrase` if there is none yet,
+    /// and returns the generation its idle timer should watch.
+    fn unlock(&self, passphrase: &str, lock_after: Duration) -> Result<u64, BridgeError> {
+        let rejected = |reason: &str| BridgeError::Rejected {
+            kind: "vault_unlock".into(),
+            reason: reason.into(),
+        };
+        if passphrase.is_empty() {
+            return Err(rejected("the passphrase is empty"));
+        }
+        let path = lock(&self.path)
+            .clone()
+            .ok_or_else(|| rejected("no vault is configured"))?;
+
+        let (kdf, key, passwords) = match fs::read(&path) {
+            Ok(bytes) => {
+                let sealed: Sealed = serde_json::from_slice(&bytes)
+                    .map_err(|e| BridgeError::Io(format!("vault file is corrupt: {e}")))?;
+                if sealed.version != VERSION {
+                    return Err(BridgeError::Io(format!(
+                        "vault file has unsupported version {}",
+                        sealed.version
+                    )));
+                }
+                let key = sealed.kdf.derive(passphrase)?;
+                let passwords =
+                    unseal(&key, &sealed).ok_or_else(|| rejected("wrong passphrase"))?;
+                (sealed.kdf, key, passwords)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let kdf = Kdf::fresh()?;
+                let key = kdf.derive(passphrase)?;
+                (kdf, key, BTreeMap::new())
+            }
+            Err(e) => return Err(BridgeError::Io(e.to_string())),
+        };
+
+        let generation = self.generations.fetch_add(1, Ordering::SeqCst) + 1;
+        let unlocked = Unlocked {
+            key,
+            kdf,
+            passwords,
+            lock_after,
+            last_used: Instant::now(),
+            generation,
+        };
+        if !path.exists() {
+            self.persist(&unlocked)?;
+        }
+        *lock(&self.open) = Some(unlocked);
+        Ok(generation)
+    }
+
+    fn lock(&self) {
+        lock(&self.open).take();
+    }
+
+    /// How long the vault unlocked as `generation` has left before it locks
+    /// itself, or `None` once it has been locked or unlocked again.
+    fn remaining(&self, generation: u64) -> Option<Duration> {
+        let open = lock(&self.open);
+        let open = open.as_ref().filter(|open| open.generation == generation)?;
+        Some(open.lock_after.saturating_sub(open.last_used.elapsed()))
+    }
+
+    /// Fills the saved password into a reconnect and marks connects so the
+    /// populator leaves passwords out of its registry. Does nothing until a
+    /// vault exists.
+    pub(super) fn prepare(
+        &self,
+        kind: &str,
+        body: &mut Value,
+    ) -> Result<Option<Ticket>, BridgeError> {
+        if !matches!(
+            kind,
+            "set_db_connect" | "set_db_reconnect" | "set_pref_delete"
+        ) || !self.exists()
+        {
+            return Ok(None);
+        }
+        let Ok(mut creds) = serde_json::from_value::<DbCreds>(camelize(body.clone())) else {
+            // Left for the populator to refuse
+            return Ok(None);
+        };
+        let password = creds.password.take().map(Zeroizing::new);
+        creds.password_source = None;
+        let mut ticket = Ticket {
+            identity: identity(&creds),
+            password,
+            creds,
+        };
+        if kind == "set_pref_delete" {
+            ticket.password = None;
+            return Ok(Some(ticket));
+        }
+
+        let mut open = lock(&self.open);
+        let Some(open) = open.as_mut() else {
+            return Err(BridgeError::Rejected {
+                kind: kind.into(),
+                reason: "the credential vault is locked".into(),
+            });
+        };
+        open.last_used = Instant::now();
+        if let Value::Object(body) = body {
+            body.insert("managed".into(), Value::Bool(true));
+            if ticket.password.is_none() {
+                if let Some(saved) = open.passwords.get(&ticket.identity) {
+                    body.insert("password".into(), Value::String(saved.clone()));
+                }
+            }
+        }
+        Ok(Some(ticket))
+    }
+
+    /// Keeps the password of a connect that worked, including one the
+    /// registry handed over, and forgets the password of a deleted
+    /// connection. Passwords never travel on in the reply. Returns the
+    /// connection whose registry copy can go now that the vault file holds
+    /// its password.
+    pub(super) fn settle(
+        &self,
+        kind: &str,
+        ticket: Option<Ticket>,
+        reply: &mut Value,
+    ) -> Option<DbCreds> {
+        let (handed_over, stored) = match reply {
+            Value::Object(reply) if kind != "set_pref_delete" => (
+                reply
+                    .remove("password")
+                    .and_then(|password| password.as_str().map(|p| Zeroizing::new(p.to_string()))),
+                reply.remove("storedPassword") == Some(Value::Bool(true)),
+            ),
+            _ => (None, false),
+        };
+        let ticket = ticket?;
+
+        let mut open = lock(&self.open);
+        let Some(open) = open.as_mut() else {
+            if stored {
+                log::warn!(
+                    "vault locked before it could keep the password of {}; the registry keeps it",
+                    ticket.identity
+                );
+            }
+            return None;
+        };
+        if kind == "set_pref_delete" {
+            if open.passwords.remove(&ticket.identity).is_some() {
+                if let Err(e) = self.persist(open) {
+                    log::error!("failed to save the credential vault: {e}");
+                }
+            }
+            return None;
+        }
+        let password = ticket.password.or(handed_over)?;
+        let previous = open
+            .passwords
+            .insert(ticket.identity.clone(), password.to_string());
+        if previous.as_deref() != Some(password.as_str()) {
+            if let Err(e) = self.persist(open) {
+                log::error!("failed to save the credential vault: {e}");
+                // What is kept in memory matches the file, so the next
+                // settle tries again
+                match previous {
+                    Some(previous) => open.passwords.insert(ticket.identity, previous),
+                    None => open.passwords.remove(&ticket.identity),
+                };
+                return None;
+            }
+        }
+        stored.then_some(ticket.creds)
+    }
+
+    /// Keeps the registry's passwords of connections the vault has none for,
+    /// and returns every connection whose registry copy can go.
+    fn take_over(&self, stored: Vec<DbCreds>) -> Vec<DbCreds> {
+        let mut open = lock(&self.open);
+        let Some(open) = open.as_mut() else {
+            return Vec::new();
+        };
+        let mut added = Vec::new();
+        let mut taken = Vec::new();
+        for mut creds in stored {
+            let Some(password) = creds.password.take().map(Zeroizing::new) else {
+                continue;
+            };
+            let identity = identity(&creds);
+            if !open.passwords.contains_key(&identity) {
+                open.passwords
+                    .insert(identity.clone(), password.to_string());
+                added.push(identity);
+            }
+            taken.push(creds);
+        }
+        if !added.is_empty() {
+            if let Err(e) = self.persist(open) {
+                log::error!("failed to save the credential vault: {e}");
+                for identity in &added {
+                    open.passwords.remove(identity);
+                }
+                return Vec::new();
+            }
+            log::info!("took {} passwords over from the registry", added.len());
+        }
+        taken
+    }
+
+    /// Seals `open` under a fresh nonce and replaces the vault file with it.
+    fn persist(&self, open: &Unlocked) -> Result<(), BridgeError> {
+        let path = lock(&self.path)
+            .clone()
+            .ok_or_else(|| BridgeError::Io("no vault is configured".into()))?;
+        let nonce = random::<NONCE_LEN>()?;
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&open.passwords).map_err(|e| BridgeError::Io(e.to_string()))?,
+        );
+        let ciphertext = XChaCha20Poly1305::new((&*open.key).into())
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| BridgeError::Io("failed to encrypt the vault".into()))?;
+        let sealed = Sealed {
+            version: VERSION,
+            kdf: open.kdf.clone(),
+            nonce: hex(&nonce),
+            ciphertext: hex(&ciphertext),
+        };
+
+        let io = |e: io::Error| BridgeError::Io(e.to_string());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io)?;
+        }
+        // Written aside and renamed over, so a crash never leaves half a vault
+        let staging = path.with_extension("tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&staging).map_err(io)?;
+        serde_json::to_writer_pretty(&mut file, &sealed)
+            .map_err(|e| BridgeError::Io(e.to_string()))?;
+        file.flush().and_then(|_| file.sync_all()).map_err(io)?;
+        fs::rename(&staging, &path).map_err(io)
+    }
+}
+
+fn unseal(key: &Key, sealed: &Sealed) -> Option<BTreeMap<String, String>> {
+    let nonce = unhex(&sealed.nonce).filter(|nonce| nonce.len() == NONCE_LEN)?;
+    let ciphertext = unhex(&sealed.ciphertext)?;
+    let plaintext = Zeroizing::new(
+        XChaCha20Poly1305::new((&**key).into())
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .ok()?,
+    );
+    serde_json::from_slice(&plaintext).ok()
+}
+
+pub(super) fn identity(creds: &DbCreds) -> String {
+    format!(
+        "{}@{}:{}/{}",
+        creds.user, creds.host, creds.port, creds.name
+    )
+}
+
+fn random<const N: usize>() -> Result<[u8; N], BridgeError> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| BridgeError::Io(e.to_string()))?;
+    Ok(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Locks the vault unlocked as `generation` once it has sat idle long enough.
+fn watch_idle(bridge: Weak<Bridge>, generation: u64) {
+    loop {
+        let Some(bridge) = bridge.upgrade() else {
+            return;
+        };
+        match bridge.vault.remaining(generation) {
+            None => return,
+            Some(left) if left.is_zero() => {
+                bridge.vault.lock();
+                log::info!("credential vault locked after sitting idle");
+                return;
+            }
+            Some(left) => {
+                drop(bridge);
+                thread::sleep(left);
+            }
+        }
+    }
+}
+
+impl Bridge {
+    /// Keeps connection passwords in the vault at `path` from now on.
+    pub fn use_vault(&self, path: PathBuf) {
+        self.vault.lock();
+        *lock(&self.vault.path) = Some(path);
+    }
+
+    pub fn vault_status(&self) -> VaultStatus {
+        self.vault.status()
+    }
+
+    /// Unlocks the vault, or creates it under `passphrase` when there is
+    /// none, until it has been idle for `lock_after`.
+    pub fn unlock_vault(
+        self: &Arc<Self>,
+        passphrase: &str,
+        lock_after: Duration,
+    ) -> Result<VaultStatus, BridgeError> {
+        let generation = self.vault.unlock(passphrase, lock_after)?;
+        let bridge = Arc::downgrade(self);
+        thread::Builder::new()
+            .name("vault-idle".into())
+            .spawn(move || watch_idle(bridge, generation))
+            .map_err(|e| BridgeError::Io(e.to_string()))?;
+        let bridge = self.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = bridge.take_over_registry_passwords().await {
+                log::warn!("failed to take passwords over from the registry: {e}");
+            }
+        });
+        Ok(self.vault.status())
+    }
+
+    pub fn lock_vault(&self) -> VaultStatus {
+        self.vault.lock();
+        self.vault.status()
+    }
+
+    /// Moves the passwords the registry still holds into the vault.
+    async fn take_over_registry_passwords(&self) -> Result<(), BridgeError> {
+        let stored = self
+            .round_trip("get_pref_passwords", json!({}), RequestOptions::default())
+            .await?;
+        let stored: Vec<DbCreds> = serde_json::from_value(camelize(stored)).map_err(|e| {
+            BridgeError::Protocol(format!("unexpected get_pref_passwords response: {e}"))
+        })?;
+        for creds in self.vault.take_over(stored) {
+            self.forget_registry_password(&creds).await;
+        }
+        Ok(())
+    }
+
+    /// Has the registry forget a password the vault file now holds. A copy
+    /// it keeps anyway is handed over again next time.
+    pub(super) async fn forget_registry_password(&self, creds: &DbCreds) {
+        let body = json!({
+            "name": creds.name,
+            "host": creds.host,
+            "port": creds.port,
+            "user": creds.user,
+            "dialect": creds.dialect,
+        });
+        let forgot = self
+            .round_trip("set_pref_forget_password", body, RequestOptions::default())
+            .await;
+        if let Err(e) = forgot {
+            log::warn!("registry kept the password of {}: {e}", identity(creds));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    io::Write,
+    process::ChildStdin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use super::{framing::Framing, journal::Direction, lock, redact::log_traffic, Bridge, BridgeError};
+
+/// Messages waiting to be written before senders start waiting for room.
+pub const QUEUE_CAPACITY: usize = 64;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct QueueStats {
+    pub depth: usize,
+    pub capacity: usize,
+}
+
+/// Owns the child's stdin and writes queued messages in order until the
+/// queue is closed or the pipe breaks. Messages are length-framed once
+/// `framed` is set.
+pub(super) fn spawn_writer(
+    mut stdin: ChildStdin,
+    framed: Arc<AtomicBool>,
+) -> (mpsc::Sender<String>, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel::<String>(QUEUE_CAPACITY);
+    let handle = thread::spawn(move || {
+        while let Some(message) = rx.blocking_recv() {
+            let framing = match framed.load(Ordering::SeqCst) {
+                true => Framing::Length,
+                false => Framing::Line,
+            };
+            let frame = framing.encode(&message);
+            if let Err(e) = stdin.write_all(&frame).and_then(|_| stdin.flush()) {
+                log::warn!("failed to write to populator: {e}");
+                break;
+            }
+        }
+    });
+    (tx, handle)
+}
+
+impl Bridge {
+    fn writer(&self) -> Result<mpsc::Sender<String>, BridgeError> {
+        lock(&self.writer).clone().ok_or(BridgeError::Missing)
+    }
+
+    /// Queues a line for the populator, waiting for room when the queue is full.
+    pub(super) async fn enqueue(&self, line: String) -> Result<(), BridgeError> {
+        self.journal.record(Direction::Out, &line);
+        log_traffic(Direction::Out, &line);
+        self.writer()?
+            .send(line)
+            .await
+            .map_err(|_| BridgeError::Missing)
+    }
+
+    /// Queues a line without waiting, for callers that can't be async.
+    pub(super) fn try_enqueue(&self, line: String) -> Result<(), BridgeError> {
+        self.journal.record(Direction::Out, &line);
+        log_traffic(Direction::Out, &line);
+        self.writer()?.try_send(line).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => BridgeError::QueueFull,
+            mpsc::error::TrySendError::Closed(_) => BridgeError::Missing,
+        })
+    }
+
+    pub fn queue_stats(&self) -> QueueStats {
+        match lock(&self.writer).as_ref() {
+            Some(tx) => QueueStats {
+                depth: tx.max_capacity() - tx.capacity(),
+                capacity: tx.max_capacity(),
+            },
+            None => QueueStats {
+                depth: 0,
+                capacity: QUEUE_CAPACITY,
+            },
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Headless subcommands that drive the populator from a terminal, for CI.
+//!
+//! Each invocation starts its own populator, so `export` and `insert`
+//! generate the table first and take the same flags as `generate`. The spec
+//! used is the one saved for the table in the app.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::bridge::{
+    self, BridgeBuilder, BridgeError, BridgeState, EventSink, Shared, DONE_EVENT, PROGRESS_EVENT,
+};
+use crate::protocol::{
+    DbCreds, ErrorLevel, GenDone, GenProgressEvent, GenStatus, PacketExport, PacketRef,
+    PendingWrites, SpecRef, TablePacket, TableSpec,
+};
+
+pub const USAGE: &str = "\
+usage: datasmith <command> [options]
+
+commands:
+  connections list                 list saved connections
+  generate <job>                   generate rows from the table's saved spec
+  export <job> --out FILE          generate, then write the rows as SQL
+          [--format sql]
+  insert <job> [--commit]          generate, then insert the rows; rolled
+                                   back unless --commit is given
+
+job options:
+  --connection NAME                saved connection to use
+  --table TABLE                    table whose saved spec to run
+  --rows N                         rows to generate (default: the spec's)
+
+environment:
+  DATASMITH_VAULT_PASSPHRASE       unlocks the app's credential vault, when
+                                   saved connections keep passwords there
+
+exit codes: 0 ok, 1 failure, 2 usage error, 3 generation reported errors";
+
+/// Passphrase of the app's credential vault, for runs without a prompt.
+pub const VAULT_PASSPHRASE_ENV: &str = "DATASMITH_VAULT_PASSPHRASE";
+
+const COMMANDS: &[&str] = &["connections", "generate", "export", "insert", "help"];
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Matches the page size the app generates with.
+const PAGE_SIZE: u32 = 250;
+
+enum Command {
+    Help,
+    ListConnections,
+    Generate(Job),
+    Export(Job, PathBuf),
+    Insert(Job, bool),
+}
+
+struct Job {
+    connection: String,
+    table: String,
+    rows: Option<u32>,
+}
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+    /// Generation finished but reported errors, already printed.
+    Rejected,
+}
+
+impl CliError {
+    fn code(&self) -> i32 {
+        match self {
+            CliError::Failed(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Rejected => 3,
+        }
+    }
+}
+
+impl From<BridgeError> for CliError {
+    fn from(e: BridgeError) -> Self {
+        CliError::Failed(e.to_string())
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{msg}\n\n{USAGE}"),
+            CliError::Failed(msg) => f.write_str(msg),
+            CliError::Rejected => f.write_str("generation reported errors"),
+        }
+    }
+}
+
+/// Runs the subcommand in `args` (without the program name) and returns its
+/// exit code, or `None` when `args` doesn't name one and the app should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    if !args
+        .first()
+        .is_some_and(|arg| COMMANDS.contains(&arg.as_str()))
+    {
+        return None;
+    }
+    attach_console();
+
+    let result = parse(args).and_then(|command| match command {
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        command => execute(command),
+    });
+    Some(match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("datasmith: {e}");
+            e.code()
+        }
+    })
+}
+
+// Release builds use the GUI subsystem, which starts without a console.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+fn parse(args: &[String]) -> Result<Command, CliError> {
+    let (command, rest) = args.split_first().expect("checked by run");
+    let mut flags = Flags::parse(rest)?;
+
+    let parsed = match command.as_str() {
+        "help" => Command::Help,
+        "connections" => match flags.positional.as_slice() {
+            [action] if action == "list" => {
+                flags.positional.clear();
+                Command::ListConnections
+            }
+            _ => return Err(CliError::Usage("expected `connections list`".into())),
+        },
+        "generate" => Command::Generate(flags.job()?),
+        "export" => {
+            let job = flags.job()?;
+            if let Some(format) = flags.value("format")? {
+                if !format.eq_ignore_ascii_case("sql") {
+                    return Err(CliError::Usage(format!(
+                        "unsupported export format {format}; only sql is supported"
+                    )));
+                }
+            }
+            Command::Export(job, PathBuf::from(flags.required("out")?))
+        }
+        "insert" => {
+            let job = flags.job()?;
+            Command::Insert(job, flags.switch("commit")?)
+        }
+        _ => unreachable!("checked by run"),
+    };
+    flags.finish()?;
+    Ok(parsed)
+}
+
+/// `--name value`, `--name=value` and bare `--name` switches, plus positionals.
+struct Flags {
+    named: HashMap<String, Option<String>>,
+    positional: Vec<String>,
+}
+
+impl Flags {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut flags = Flags {
+            named: HashMap::new(),
+            positional: Vec::new(),
+        };
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                flags.positional.push(arg.clone());
+                continue;
+            };
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, args.next_if(|next| !next.starts_with("--")).cloned()),
+            };
+            if flags.named.insert(name.to_string(), value).is_some() {
+                return Err(CliError::Usage(format!("--{name} given more than once")));
+            }
+        }
+        Ok(flags)
+    }
+
+    fn value(&mut self, name: &str) -> Result<Option<String>, CliError> {
+        match self.named.remove(name) {
+            Some(Some(value)) => Ok(Some(value)),
+            Some(None) => Err(CliError::Usage(format!("--{name} needs a value"))),
+            None => Ok(None),
+        }
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, CliError> {
+        self.value(name)?
+            .ok_or_else(|| CliError::Usage(format!("--{name} is required")))
+    }
+
+    fn switch(&mut self, name: &str) -> Result<bool, CliError> {
+        match self.named.remove(name) {
+            Some(Some(value)) => Err(CliError::Usage(format!(
+                "--{name} takes no value, got {value}"
+            ))),
+            Some(None) => Ok(true),
+            None => Ok(false),
+        }
+    }
+
+    fn job(&mut self) -> Result<Job, CliError> {
+        let rows = match self.value("rows")? {
+            Some(rows) => Some(rows.parse().map_err(|_| {
+                CliError::Usage(format!("--rows expects a whole number, got {rows}"))
+            })?),
+            None => None,
+        };
+        Ok(Job {
+            connection: self.required("connection")?,
+            table: self.required("table")?,
+            rows,
+        })
+    }
+
+    fn finish(self) -> Result<(), CliError> {
+        if let Some(arg) = self.positional.first() {
+            return Err(CliError::Usage(format!("unexpected argument {arg}")));
+        }
+        match self.named.keys().next() {
+            Some(name) => Err(CliError::Usage(format!("unknown option --{name}"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Prints progress to stderr and hands finished jobs to the waiting command.
+struct Terminal {
+    done: mpsc::Sender<GenDone>,
+    tty: bool,
+}
+
+impl EventSink for Terminal {
+    fn emit_json(&self, event: &str, payload: Value) {
+        match event {
+            PROGRESS_EVENT => {
+                if let Ok(progress) = serde_json::from_value::<GenProgressEvent>(payload) {
+                    self.progress(&progress);
+                }
+            }
+            DONE_EVENT => {
+                if let Ok(done) = serde_json::from_value::<GenDone>(payload) {
+                    if self.tty {
+                        eprintln!();
+                    }
+                    self.done.send(done).ok();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Terminal {
+    fn progress(&self, progress: &GenProgressEvent) {
+        let mut line = format!(
+            "{}: {}/{} rows",
+            progress.status, progress.row, progress.total
+        );
+        if let Some(column) = &progress.column {
+            line.push_str(&format!(" ({column})"));
+        }
+        if let Some(eta) = progress.eta_ms {
+            line.push_str(&format!(", {}s left", eta.div_ceil(1000)));
+        }
+        // Redraw in place on a terminal, one line per update in CI logs
+        let mut stderr = io::stderr().lock();
+        if self.tty {
+            write!(stderr, "\r\x1b[2K{line}").ok();
+        } else {
+            writeln!(stderr, "{line}").ok();
+        }
+        stderr.flush().ok();
+    }
+}
+
+fn execute(command: Command) -> Result<(), CliError> {
+    let (done_tx, done_rx) = mpsc::channel();
+    let terminal = Arc::new(Terminal {
+        done: done_tx,
+        tty: io::stderr().is_terminal(),
+    });
+    let sidecar = bridge::populator_sidecar()
+        .map_err(|e| CliError::Failed(format!("failed to locate populator: {e}")))?;
+    let (bridge, supervisor) = BridgeBuilder::new(sidecar, app_data_dir()?)
+        .events(terminal)
+        .spawn();
+
+    let session = Session {
+        bridge: bridge.clone(),
+        done: done_rx,
+    };
+    let result = session
+        .wait_ready()
+        .and_then(|()| session.unlock_vault())
+        .and_then(|()| session.execute(command));
+
+    bridge.shutdown(SHUTDOWN_TIMEOUT);
+    supervisor.join().ok();
+    result
+}
+
+/// Where the desktop app keeps its data, resolved the way Tauri does for
+/// its identifier, so both share the audit log, vault and credential
+/// sources.
+fn app_data_dir() -> Result<PathBuf, CliError> {
+    let config: Value = serde_json::from_str(include_str!("../tauri.conf.json"))
+        .map_err(|e| CliError::Failed(format!("invalid app config: {e}")))?;
+    let identifier = config["identifier"]
+        .as_str()
+        .ok_or_else(|| CliError::Failed("app config has no identifier".into()))?;
+    dirs::data_dir()
+        .map(|dir| dir.join(identifier))
+        .ok_or_else(|| CliError::Failed("no data directory for this user".into()))
+}
+
+struct Session {
+    bridge: Shared,
+    done: mpsc::Receiver<GenDone>,
+}
+
+impl Session {
+    fn call<T: DeserializeOwned>(&self, kind: &str, body: impl Serialize) -> Result<T, CliError> {
+        Ok(tauri::async_runtime::block_on(
+            self.bridge.request_as(kind, body),
+        )?)
+    }
+
+    fn unlock_vault(&self) -> Result<(), CliError> {
+        let Ok(passphrase) = std::env::var(VAULT_PASSPHRASE_ENV) else {
+            return Ok(());
+        };
+        self.bridge
+            .unlock_vault(&passphrase, bridge::DEFAULT_LOCK_AFTER)?;
+        Ok(())
+    }
+
+    fn wait_ready(&self) -> Result<(), CliError> {
+        let deadline = Instant::now() + READY_TIMEOUT;
+        loop {
+            let status = self.bridge.status();
+            match status.state {
+                BridgeState::Ready => return Ok(()),
+                // The supervisor would keep restarting it; a CI run should fail instead
+                BridgeState::Crashed | BridgeState::Incompatible => {
+                    return Err(CliError::Failed(
+                        status
+                            .message
+                            .unwrap_or_else(|| "populator failed to start".into()),
+                    ))
+                }
+                BridgeState::Starting | BridgeState::Restarting => {}
+            }
+            if Instant::now() >= deadline {
+                return Err(CliError::Failed(format!(
+                    "populator not ready after {}s",
+                    READY_TIMEOUT.as_secs()
+                )));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn execute(&self, command: Command) -> Result<(), CliError> {
+        match command {
+            Command::Help => unreachable!("handled by run"),
+            Command::ListConnections => {
+                for creds in self.call::<Vec<DbCreds>>("get_pref_connections", Value::Null)? {
+                    println!(
+                        "{}\t{:?}\t{}@{}:{}",
+                        creds.name, creds.dialect, creds.user, creds.host, creds.port
+                    );
+                }
+                Ok(())
+            }
+            Command::Generate(job) => self.generate(&job).map(|_| ()),
+            Command::Export(job, out) => {
+                let packet = self.generate(&job)?;
+                let path = out.to_string_lossy().into_owned();
+                let message: String = self.call(
+                    "set_db_export",
+                    PacketExport {
+                        packet_id: packet.id,
+                        path,
+                    },
+                )?;
+                println!("{message}");
+                Ok(())
+            }
+            Command::Insert(job, commit) => {
+                let packet = self.generate(&job)?;
+                let writes: PendingWrites = self.call(
+                    "set_db_insert",
+                    PacketRef {
+                        packet_id: packet.id,
+                    },
+                )?;
+                let kind = if commit {
+                    "set_db_commit"
+                } else {
+                    "set_db_rollback"
+                };
+                let message: String = self.call(kind, Value::Null)?;
+                println!("{} rows written. {message}", writes.pending_writes);
+                Ok(())
+            }
+        }
+    }
+
+    fn connect(&self, name: &str) -> Result<DbCreds, CliError> {
+        let connections: Vec<DbCreds> = self.call("get_pref_connections", Value::Null)?;
+        let mut matches = connections.into_iter().filter(|creds| creds.name == name);
+        let creds = match (matches.next(), matches.next()) {
+            (Some(creds), None) => creds,
+            (Some(_), Some(_)) => {
+                return Err(CliError::Failed(format!(
+                    "more than one saved connection is named {name}"
+                )))
+            }
+            (None, _) => {
+                return Err(CliError::Failed(format!(
+                    "no saved connection named {name}; see `datasmith connections list`"
+                )))
+            }
+        };
+        Ok(self.call("set_db_reconnect", creds)?)
+    }
+
+    fn generate(&self, job: &Job) -> Result<TablePacket, CliError> {
+        let creds = self.connect(&job.connection)?;
+        let db_id = creds.id.ok_or_else(|| {
+            CliError::Failed(format!("connection {} has no saved id", creds.name))
+        })?;
+        let spec: Option<TableSpec> = self.call(
+            "get_pref_spec",
+            SpecRef {
+                db_id,
+                table_name: job.table.clone(),
+            },
+        )?;
+        let mut spec = spec.ok_or_else(|| {
+            CliError::Failed(format!(
+                "no saved spec for {}; set the table up in the app first",
+                job.table
+            ))
+        })?;
+        if let Some(rows) = job.rows {
+            spec.no_of_entries = rows;
+        }
+        spec.page_size = PAGE_SIZE;
+
+        let started: GenStatus = self.call("get_gen_packets", spec)?;
+        let done = self.wait_done(&started.job_id)?;
+        let packet = match (done.data, done.error) {
+            (_, Some(error)) => return Err(CliError::Failed(error)),
+            (Some(packet), None) => packet,
+            (None, None) => return Err(CliError::Failed("generation returned no data".into())),
+        };
+
+        let errors = packet.errors.as_deref().unwrap_or_default();
+        for error in errors {
+            let level = match error.r#type {
+                ErrorLevel::Warning => "warning",
+                ErrorLevel::Error => "error",
+            };
+            let column = error.column.as_deref().unwrap_or(&packet.name);
+            let msg = error.msg.as_deref().unwrap_or_default();
+            eprintln!("{level}: {column}: {msg}");
+        }
+        if errors.iter().any(|e| e.r#type == ErrorLevel::Error) {
+            return Err(CliError::Rejected);
+        }
+        eprintln!(
+            "generated {} rows for {}",
+            packet.total_entries, packet.name
+        );
+        Ok(packet)
+    }
+
+    fn wait_done(&self, job_id: &str) -> Result<GenDone, CliError> {
+        loop {
+            match self.done.recv_timeout(POLL_INTERVAL) {
+                Ok(done) if done.job_id == job_id => return Ok(done),
+                Ok(_) => {}
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if self.bridge.status().state != BridgeState::Ready {
+                        return Err(CliError::Failed(
+                            "populator stopped during generation".into(),
+                        ));
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(CliError::Failed(
+                        "populator stopped during generation".into(),
+                    ))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! One Tauri command per populator request kind.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State, Webview};
+
+use crate::bridge::{confirm_sql_request, Bridge, BridgeError, RequestOptions, Shared};
+use crate::protocol::*;
+
+type Reply<T> = Result<T, BridgeError>;
+
+// Checked against the window's allowlist before it reaches the populator.
+async fn call<T: DeserializeOwned>(
+    bridge: &Bridge,
+    webview: &Webview,
+    kind: &str,
+    body: impl Serialize,
+) -> Reply<T> {
+    let options = RequestOptions::from_window(webview.label());
+    bridge.request_as_with(kind, body, options).await
+}
+
+#[tauri::command]
+pub async fn ping(webview: Webview, bridge: State<'_, Shared>) -> Reply<String> {
+    call(&bridge, &webview, "ping", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_db_info(webview: Webview, bridge: State<'_, Shared>) -> Reply<DbCreds> {
+    call(&bridge, &webview, "get_db_info", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_db_last_connected(
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<Option<DbCreds>> {
+    call(&bridge, &webview, "get_db_last_connected", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn set_db_connect(
+    creds: DbCreds,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<DbCreds> {
+    call(&bridge, &webview, "set_db_connect", creds).await
+}
+
+#[tauri::command]
+pub async fn set_db_reconnect(
+    creds: DbCreds,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<DbCreds> {
+    call(&bridge, &webview, "set_db_reconnect", creds).await
+}
+
+#[tauri::command]
+pub async fn set_db_disconnect(webview: Webview, bridge: State<'_, Shared>) -> Reply<String> {
+    call(&bridge, &webview, "set_db_disconnect", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_pref_connections(
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<Vec<DbCreds>> {
+    call(&bridge, &webview, "get_pref_connections", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn set_pref_delete(
+    creds: DbCreds,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<String> {
+    call(&bridge, &webview, "set_pref_delete", creds).await
+}
+
+#[tauri::command]
+pub async fn get_db_tables(webview: Webview, bridge: State<'_, Shared>) -> Reply<Vec<TableEntry>> {
+    call(&bridge, &webview, "get_db_tables", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_db_table(
+    name: String,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<TableMetadata> {
+    call(&bridge, &webview, "get_db_table", TableRef { name }).await
+}
+
+#[tauri::command]
+pub async fn get_gen_methods(webview: Webview, bridge: State<'_, Shared>) -> Reply<Vec<String>> {
+    call(&bridge, &webview, "get_gen_methods", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_gen_packets(
+    spec: TableSpec,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<GenStatus> {
+    call(&bridge, &webview, "get_gen_packets", spec).await
+}
+
+#[tauri::command]
+pub async fn get_gen_packet(
+    packet_id: String,
+    page: u32,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<TablePacket> {
+    call(
+        &bridge,
+        &webview,
+        "get_gen_packet",
+        PacketPage { packet_id, page },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn poll_gen_status(
+    job_id: String,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<GenStatus> {
+    call(&bridge, &webview, "poll_gen_status", JobRef { job_id }).await
+}
+
+#[tauri::command]
+pub async fn clear_gen_packets(webview: Webview, bridge: State<'_, Shared>) -> Reply<String> {
+    call(&bridge, &webview, "clear_gen_packets", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_pref_spec(
+    db_id: i32,
+    table_name: String,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<Option<TableSpec>> {
+    call(
+        &bridge,
+        &webview,
+        "get_pref_spec",
+        SpecRef { db_id, table_name },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_pref_rows(webview: Webview, bridge: State<'_, Shared>) -> Reply<Vec<UsageInfo>> {
+    call(&bridge, &webview, "get_pref_rows", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_sql_banner(webview: Webview, bridge: State<'_, Shared>) -> Reply<SqlBanner> {
+    call(&bridge, &webview, "get_sql_banner", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn run_sql_query(
+    sql: String,
+    app: AppHandle,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<Vec<String>> {
+    let body =
+        serde_json::to_value(SqlQuery { sql }).map_err(|e| BridgeError::Protocol(e.to_string()))?;
+    confirm_sql_request(&app, webview.label(), &body).await?;
+    call(&bridge, &webview, "run_sql_query", body).await
+}
+
+#[tauri::command]
+pub async fn get_logs_read(
+    lines: u32,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<Vec<String>> {
+    call(&bridge, &webview, "get_logs_read", LogsRead { lines }).await
+}
+
+#[tauri::command]
+pub async fn set_logs_clear(webview: Webview, bridge: State<'_, Shared>) -> Reply<Vec<String>> {
+    call(&bridge, &webview, "set_logs_clear", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn set_db_insert(
+    packet_id: String,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<PendingWrites> {
+    call(&bridge, &webview, "set_db_insert", PacketRef { packet_id }).await
+}
+
+#[tauri::command]
+pub async fn set_db_export(
+    packet_id: String,
+    path: String,
+    webview: Webview,
+    bridge: State<'_, Shared>,
+) -> Reply<String> {
+    call(
+        &bridge,
+        &webview,
+        "set_db_export",
+        PacketExport { packet_id, path },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn set_db_commit(webview: Webview, bridge: State<'_, Shared>) -> Reply<String> {
+    call(&bridge, &webview, "set_db_commit", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn set_db_rollback(webview: Webview, bridge: State<'_, Shared>) -> Reply<String> {
+    call(&bridge, &webview, "set_db_rollback", Value::Null).await
+}
+
+#[tauri::command]
+pub async fn get_uncommitted_db(webview: Webview, bridge: State<'_, Shared>) -> Reply<u32> {
+    call(&bridge, &webview, "get_uncommitted_db", Value::Null).await
+}
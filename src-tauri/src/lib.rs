@@ -1,16 +1,14 @@
+pub mod app;
+pub mod bridge;
+pub mod cli;
+mod commands;
+pub mod logging;
+pub mod protocol;
+mod shell;
+
+pub use app::DataSmithApp;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
-            Ok(())
-        })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+    DataSmithApp::new().run(tauri::generate_context!());
 }
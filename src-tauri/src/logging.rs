@@ -0,0 +1,195 @@
+//! The shell's own log, kept in every build.
+//!
+//! Records are JSON lines in a rotating file next to the bridge's logs, each
+//! with its time, level, target and message. The level can be changed while
+//! the app runs, starting from [`LOG_ENV`] or `info`. Records are echoed to
+//! stderr only when [`LOG_CONSOLE_ENV`] asks for it.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde::{Deserialize, Serialize};
+
+use crate::bridge::{lock, RotatingFile};
+
+/// Level to start logging at, e.g. `debug` to include bridge traffic.
+pub const LOG_ENV: &str = "DATASMITH_LOG";
+/// Set to also write records to stderr, e.g. while developing.
+pub const LOG_CONSOLE_ENV: &str = "DATASMITH_LOG_CONSOLE";
+
+const FILE_NAME: &str = "datasmith.log";
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const KEEP_LOGS: usize = 3;
+// Dependencies are only heard from at info and above
+const OWN_TARGETS: &[&str] = &["app_lib", "bridge", "DataSmith"];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogQuery {
+    /// The least severe level to include, e.g. `warn`.
+    pub level: Option<String>,
+    /// Keeps records whose target starts with this.
+    pub target: Option<String>,
+    /// Keeps records whose message contains this, ignoring case.
+    pub contains: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub since: Option<u64>,
+    /// Keeps only the most recent records.
+    pub limit: Option<usize>,
+}
+
+struct FileLogger {
+    path: PathBuf,
+    file: Mutex<RotatingFile>,
+    console: bool,
+}
+
+type Follower = Box<dyn Fn(&LogRecord) + Send + Sync>;
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+static FOLLOWERS: Mutex<Vec<Follower>> = Mutex::new(Vec::new());
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && (metadata.level() <= Level::Info
+                || OWN_TARGETS
+                    .iter()
+                    .any(|own| metadata.target().starts_with(own)))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let record = LogRecord {
+            at: now_ms(),
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        if self.console {
+            eprintln!("[{} {}] {}", record.level, record.target, record.message);
+        }
+        for follower in lock(&FOLLOWERS).iter() {
+            follower(&record);
+        }
+        let Ok(line) = serde_json::to_string(&record) else {
+            return;
+        };
+        // Nowhere left to report a failure to
+        lock(&self.file).write_line(&line).ok();
+    }
+
+    fn flush(&self) {}
+}
+
+/// Starts logging to `log_dir`; fails if a logger is already installed.
+pub fn init(log_dir: &Path) -> Result<(), SetLoggerError> {
+    let level = std::env::var(LOG_ENV)
+        .ok()
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(LevelFilter::Info);
+    let path = log_dir.join(FILE_NAME);
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        file: Mutex::new(RotatingFile::new(path.clone(), MAX_LOG_BYTES, KEEP_LOGS)),
+        path,
+        console: std::env::var_os(LOG_CONSOLE_ENV).is_some(),
+    });
+    log::set_logger(logger)?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+pub fn level() -> LevelFilter {
+    log::max_level()
+}
+
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+    log::info!("log level set to {level}");
+}
+
+/// Calls `follower` with every record logged from now on. It must not log
+/// itself.
+pub fn follow(follower: impl Fn(&LogRecord) + Send + Sync + 'static) {
+    lock(&FOLLOWERS).push(Box::new(follower));
+}
+
+/// Records matching `query` from the log and its rotations, oldest first.
+pub fn read(query: &LogQuery) -> io::Result<Vec<LogRecord>> {
+    let Some(logger) = LOGGER.get() else {
+        return Ok(Vec::new());
+    };
+    let level = query
+        .level
+        .as_deref()
+        .map(Level::from_str)
+        .transpose()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let contains = query.contains.as_deref().map(str::to_lowercase);
+
+    let files = (1..=KEEP_LOGS).rev().map(|i| {
+        let mut name = logger.path.clone().into_os_string();
+        name.push(format!(".{i}"));
+        PathBuf::from(name)
+    });
+    let mut records = Vec::new();
+    for path in files.chain([logger.path.clone()]) {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(file).lines() {
+            let Ok(record) = serde_json::from_str::<LogRecord>(&line?) else {
+                continue;
+            };
+            let severe_enough = || {
+                let Some(level) = level else {
+                    return true;
+                };
+                Level::from_str(&record.level).is_ok_and(|own| own <= level)
+            };
+            if query.since.is_some_and(|since| record.at < since)
+                || !severe_enough()
+                || query
+                    .target
+                    .as_deref()
+                    .is_some_and(|target| !record.target.starts_with(target))
+                || contains
+                    .as_deref()
+                    .is_some_and(|needle| !record.message.to_lowercase().contains(needle))
+            {
+                continue;
+            }
+            records.push(record);
+        }
+    }
+    if let Some(limit) = query.limit {
+        records.drain(..records.len().saturating_sub(limit));
+    }
+    Ok(records)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
@@ -0,0 +1,346 @@
+//! Request and response bodies of the populator protocol.
+//!
+//! These mirror the pydantic models in `core/utils/types.py` and what the
+//! `_handle_*` methods of `core/runner.py` return. TypeScript bindings for
+//! the frontend are generated from them into `src/bindings` by `cargo test`.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "UPPERCASE")]
+#[ts(export)]
+pub enum DbDialect {
+    Mysql,
+    Postgresql,
+    #[default]
+    Unknown,
+    Mssql,
+    Oracle,
+    Mariadb,
+    Firebird,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct DbCreds {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub id: Option<i32>,
+    pub host: String,
+    pub user: String,
+    #[serde(deserialize_with = "string_or_number")]
+    pub port: String,
+    pub name: String,
+    #[serde(default)]
+    pub dialect: DbDialect,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub password: Option<String>,
+    /// Where to read the password instead. Only saved through the app and
+    /// resolved by the shell, so requests that carry one are refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub password_source: Option<CredentialSource>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[ts(export)]
+pub enum CredentialSource {
+    /// An environment variable of the shell.
+    Env { name: String },
+    /// A `KEY=value` line in a `.env` file.
+    DotEnv { path: String, key: String },
+    /// The first line a shell command prints, e.g. `pass show db/staging`.
+    Command { command: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ForeignKeyRef {
+    pub table: String,
+    pub column: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ColumnMetadata {
+    pub name: String,
+    pub r#type: String,
+    pub unique: bool,
+    pub multi_unique: Option<Vec<String>>,
+    pub primary_key: bool,
+    pub nullable: bool,
+    pub default: Option<String>,
+    pub autoincrement: bool,
+    pub computed: bool,
+    pub foreign_keys: Option<ForeignKeyRef>,
+    pub length: Option<i32>,
+    pub precision: Option<i32>,
+    pub scale: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TableMetadata {
+    pub name: String,
+    pub parents: Vec<String>,
+    pub columns: Vec<ColumnMetadata>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TableEntry {
+    pub name: String,
+    pub parents: u32,
+    #[ts(type = "number")]
+    pub rows: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum GeneratorType {
+    Faker,
+    Regex,
+    Foreign,
+    Python,
+    Constant,
+    Autoincrement,
+    Computed,
+    Null,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub generator: Option<String>,
+    pub r#type: Option<GeneratorType>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TableSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub db_id: Option<i32>,
+    pub page_size: u32,
+    pub name: String,
+    pub no_of_entries: u32,
+    pub columns: Vec<ColumnSpec>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum ErrorLevel {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ErrorPacket {
+    pub msg: Option<String>,
+    pub column: Option<String>,
+    pub r#type: ErrorLevel,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TablePacket {
+    pub id: String,
+    pub name: String,
+    pub columns: Option<Vec<String>>,
+    #[ts(type = "Array<Array<string | null>>")]
+    pub entries: Vec<Vec<Value>>,
+    pub errors: Option<Vec<ErrorPacket>>,
+    pub page: u32,
+    pub page_size: u32,
+    pub total_pages: u32,
+    pub total_entries: u32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct GenProgress {
+    pub status: String,
+    pub row: u32,
+    pub total: u32,
+    pub column: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum GenState {
+    Pending,
+    Done,
+}
+
+/// Reply to `get_gen_packets` and `poll_gen_status`.
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GenStatus {
+    pub status: GenState,
+    pub message: String,
+    pub job_id: String,
+    pub data: Option<TablePacket>,
+    #[serde(default)]
+    pub progress: GenProgress,
+}
+
+/// Payload of the `gen-progress` event, throttled by the bridge.
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GenProgressEvent {
+    pub job_id: String,
+    pub status: String,
+    pub row: u32,
+    pub total: u32,
+    pub column: Option<String>,
+    pub rows_per_sec: Option<f64>,
+    #[ts(type = "number | null")]
+    pub eta_ms: Option<u64>,
+}
+
+/// Payload of the `gen-done` event; exactly one of `data` and `error` is set.
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct GenDone {
+    pub job_id: String,
+    pub data: Option<TablePacket>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SqlBanner {
+    pub log: Vec<String>,
+    pub prompt: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct UsageInfo {
+    pub table_name: String,
+    #[ts(type = "number")]
+    pub total_rows: u64,
+    #[ts(type = "number")]
+    pub new_rows: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PendingWrites {
+    pub pending_writes: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TableRef {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SpecRef {
+    pub db_id: i32,
+    pub table_name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PacketRef {
+    pub packet_id: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PacketPage {
+    pub packet_id: String,
+    pub page: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PacketExport {
+    pub packet_id: String,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct JobRef {
+    pub job_id: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SqlQuery {
+    pub sql: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LogsRead {
+    pub lines: u32,
+}
+
+/// Converts the snake_case keys the populator sends to the camelCase the
+/// frontend and these types use.
+pub fn camelize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (camel_case(&k), camelize(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(camelize).collect()),
+        other => other,
+    }
+}
+
+fn camel_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    let mut upper = false;
+    for (i, c) in key.chars().enumerate() {
+        if c == '_' && i > 0 {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// `DbCredsSchema.port` is `int | str` on the Python side.
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected a port, got {other}"
+        ))),
+    }
+}
@@ -0,0 +1,219 @@
+//! Commands for the shell itself: the bridge, the vault and the logs.
+
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+use serde_json::Value;
+use tauri::{AppHandle, State, Webview};
+
+use crate::bridge::{
+    self, AuditEntry, AuditQuery, BridgeError, BridgeStatus, Handshake, LogEntry, LogFilter,
+    QueueStats, RequestOptions, Shared, Timeouts, VaultStatus,
+};
+use crate::logging::{self, LogQuery, LogRecord};
+use crate::protocol::{CredentialSource, DbCreds};
+
+#[tauri::command]
+pub async fn request(
+    kind: String,
+    body: Option<Value>,
+    options: Option<RequestOptions>,
+    app: AppHandle,
+    webview: Webview,
+    state: State<'_, Shared>,
+) -> Result<Value, BridgeError> {
+    let body = body.unwrap_or_default();
+    if kind == "run_sql_query" {
+        bridge::confirm_sql_request(&app, webview.label(), &body).await?;
+    }
+    // Checked against the window's allowlist before it reaches the populator
+    let options = RequestOptions {
+        window: Some(webview.label().to_string()),
+        ..options.unwrap_or_default()
+    };
+    state.request(&kind, body, options).await
+}
+
+// A window can only cancel its own requests
+#[tauri::command]
+pub fn cancel_request(
+    id: String,
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<bool, BridgeError> {
+    state.check_command(webview.label(), "cancel_request")?;
+    Ok(state.cancel(&id, Some(webview.label())))
+}
+
+#[tauri::command]
+pub fn get_request_timeouts(state: State<Shared>) -> Timeouts {
+    state.timeouts()
+}
+
+#[tauri::command]
+pub fn set_request_timeouts(
+    timeouts: Timeouts,
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<(), BridgeError> {
+    state.check_command(webview.label(), "set_request_timeouts")?;
+    state.set_timeouts(timeouts);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn bridge_status(state: State<Shared>) -> BridgeStatus {
+    state.status()
+}
+
+#[tauri::command]
+pub fn bridge_handshake(state: State<Shared>) -> Option<Handshake> {
+    state.handshake()
+}
+
+#[tauri::command]
+pub fn bridge_queue_stats(state: State<Shared>) -> QueueStats {
+    state.queue_stats()
+}
+
+#[tauri::command]
+pub fn bridge_stderr_tail(lines: usize, state: State<Shared>) -> Vec<String> {
+    state.stderr_tail(lines)
+}
+
+#[tauri::command]
+pub fn bridge_journal_start(
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<PathBuf, BridgeError> {
+    state.check_command(webview.label(), "bridge_journal_start")?;
+    state.start_journal()
+}
+
+#[tauri::command]
+pub fn bridge_journal_stop(
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<Option<PathBuf>, BridgeError> {
+    state.check_command(webview.label(), "bridge_journal_stop")?;
+    Ok(state.stop_journal())
+}
+
+#[tauri::command]
+pub fn bridge_socket_path(state: State<Shared>) -> Option<PathBuf> {
+    state.socket_path()
+}
+
+// The token stays in the info file, out of reach of the webviews
+#[tauri::command]
+pub fn bridge_http_url(state: State<Shared>) -> Option<String> {
+    state.http_info().map(|info| info.url)
+}
+
+#[tauri::command]
+pub fn audit_query(
+    query: Option<AuditQuery>,
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<Vec<AuditEntry>, BridgeError> {
+    state.check_command(webview.label(), "audit_query")?;
+    state.query_audit(&query.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn vault_status(webview: Webview, state: State<Shared>) -> Result<VaultStatus, BridgeError> {
+    state.check_command(webview.label(), "vault_status")?;
+    Ok(state.vault_status())
+}
+
+// Deriving the key takes a moment, so it stays off the async workers
+#[tauri::command]
+pub async fn vault_unlock(
+    passphrase: String,
+    lock_after_secs: Option<u64>,
+    webview: Webview,
+    state: State<'_, Shared>,
+) -> Result<VaultStatus, BridgeError> {
+    state.check_command(webview.label(), "vault_unlock")?;
+    let bridge = state.inner().clone();
+    let lock_after = lock_after_secs.map_or(bridge::DEFAULT_LOCK_AFTER, Duration::from_secs);
+    tauri::async_runtime::spawn_blocking(move || bridge.unlock_vault(&passphrase, lock_after))
+        .await
+        .map_err(|e| BridgeError::Io(e.to_string()))?
+}
+
+#[tauri::command]
+pub fn vault_lock(webview: Webview, state: State<Shared>) -> Result<VaultStatus, BridgeError> {
+    state.check_command(webview.label(), "vault_lock")?;
+    Ok(state.lock_vault())
+}
+
+// Only the user, in a native dialog, can approve a source
+#[tauri::command]
+pub async fn set_credential_source(
+    creds: DbCreds,
+    source: CredentialSource,
+    app: AppHandle,
+    webview: Webview,
+) -> Result<(), BridgeError> {
+    bridge::confirm_credential_source(&app, webview.label(), &creds, source).await
+}
+
+#[tauri::command]
+pub fn credential_sources(
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<BTreeMap<String, CredentialSource>, BridgeError> {
+    state.check_command(webview.label(), "credential_sources")?;
+    Ok(state.credential_sources())
+}
+
+#[tauri::command]
+pub fn get_log_level() -> String {
+    logging::level().to_string().to_lowercase()
+}
+
+#[tauri::command]
+pub fn set_log_level(
+    level: String,
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<String, BridgeError> {
+    state.check_command(webview.label(), "set_log_level")?;
+    let filter = level.parse().map_err(|_| BridgeError::Rejected {
+        kind: "set_log_level".into(),
+        reason: format!("unknown log level {level:?}"),
+    })?;
+    logging::set_level(filter);
+    Ok(get_log_level())
+}
+
+#[tauri::command]
+pub fn logs_query(query: Option<LogQuery>) -> Result<Vec<LogRecord>, BridgeError> {
+    logging::read(&query.unwrap_or_default()).map_err(|e| match e.kind() {
+        std::io::ErrorKind::InvalidInput => BridgeError::Rejected {
+            kind: "logs_query".into(),
+            reason: e.to_string(),
+        },
+        _ => BridgeError::Io(e.to_string()),
+    })
+}
+
+// The entries the window missed come back; new ones arrive as events
+#[tauri::command]
+pub fn logs_follow(
+    filter: Option<LogFilter>,
+    webview: Webview,
+    state: State<Shared>,
+) -> Result<Vec<LogEntry>, BridgeError> {
+    state.follow_logs(webview.label(), filter.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn logs_unfollow(webview: Webview, state: State<Shared>) {
+    state.unfollow_logs(webview.label())
+}
+
+#[tauri::command]
+pub fn bridge_journal_path(state: State<Shared>) -> Option<PathBuf> {
+    state.journal_path()
+}
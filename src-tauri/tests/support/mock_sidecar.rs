@@ -0,0 +1,209 @@
+//! A scripted stand-in for the populator, which the bridge tests run as
+//! their sidecar so they need neither Python nor a window.
+//!
+//! The first argument names a mode: `framed` negotiates length framing like
+//! the real populator, `line` keeps to plain lines and `legacy` predates the
+//! handshake. The second, if any, is the directory it says it keeps its log
+//! files in.
+
+use std::{
+    collections::HashSet,
+    env, fs,
+    io::{self, BufRead, Read, Write},
+    process,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mode = args.next().unwrap_or_else(|| "framed".into());
+    serve(&mode, args.next());
+}
+
+struct Output {
+    framed: bool,
+}
+
+impl Output {
+    fn write(&self, message: &Value) {
+        let message = message.to_string();
+        let mut stdout = io::stdout().lock();
+        if self.framed {
+            write!(stdout, "#{}\n{message}\n", message.len()).unwrap();
+        } else {
+            writeln!(stdout, "{message}").unwrap();
+        }
+        stdout.flush().unwrap();
+    }
+}
+
+fn read_message(stdin: &mut impl BufRead) -> Option<String> {
+    let mut header = String::new();
+    if stdin.read_line(&mut header).ok()? == 0 {
+        return None;
+    }
+    let trimmed = header.trim_end();
+    match trimmed.strip_prefix('#').map(str::parse::<u64>) {
+        Some(Ok(len)) => {
+            let mut payload = String::new();
+            stdin.by_ref().take(len).read_to_string(&mut payload).ok()?;
+            stdin.read_line(&mut String::new()).ok()?;
+            Some(payload)
+        }
+        _ => Some(trimmed.to_string()),
+    }
+}
+
+/// Plays the populator on stdin/stdout until stdin closes or `exit`.
+fn serve(mode: &str, log_dir: Option<String>) {
+    let output = Arc::new(Mutex::new(Output { framed: false }));
+    let mut stdin = io::stdin().lock();
+    // Registry passwords the shell has taken over
+    let mut forgotten = HashSet::new();
+
+    while let Some(message) = read_message(&mut stdin) {
+        if message.is_empty() {
+            continue;
+        }
+        if message == "exit" {
+            output
+                .lock()
+                .unwrap()
+                .write(&json!({ "status": "ok", "payload": "exiting..." }));
+            return;
+        }
+
+        let request: Value = serde_json::from_str(&message).unwrap();
+        let id = request["id"].clone();
+        let body = request["body"].clone();
+        let ok = |payload: Value| json!({ "id": id, "status": "ok", "payload": payload });
+        let err = |error: &str| json!({ "id": id, "status": "error", "error": error });
+
+        match request["kind"].as_str().unwrap_or_default() {
+            "hello" if mode == "legacy" => {
+                output.lock().unwrap().write(&err("Unknown command: hello"))
+            }
+            "hello" => {
+                let offered = body["framing"].as_array().cloned().unwrap_or_default();
+                let framed = mode == "framed" && offered.contains(&json!("length"));
+                if let Some(dir) = &log_dir {
+                    fs::create_dir_all(dir).unwrap();
+                }
+                let mut output = output.lock().unwrap();
+                output.write(&ok(json!({
+                    "protocol": 1,
+                    "version": "mock",
+                    "kinds": ["hello", "echo", "delay", "garbage", "big", "crash", "hang", "fail"],
+                    "framing": if framed { "length" } else { "line" },
+                    "logDir": log_dir,
+                })));
+                output.framed = framed;
+            }
+            "echo" => output.lock().unwrap().write(&ok(body)),
+            "set_db_commit" => output.lock().unwrap().write(&ok(json!("committed"))),
+            "set_db_connect" => {
+                let mut payload = json!({ "name": body["name"], "sawPassword": body["password"] });
+                if body["managed"] == json!(true) && !forgotten.contains(&identity(&body)) {
+                    payload["storedPassword"] = json!(true);
+                }
+                output.lock().unwrap().write(&ok(payload))
+            }
+            // Hands its registry copy over to a vault that has none yet
+            "set_db_reconnect" => {
+                let mut payload = json!({ "sawPassword": body["password"] });
+                if body["managed"] == json!(true) && !forgotten.contains(&identity(&body)) {
+                    payload["storedPassword"] = json!(true);
+                    if body["password"].is_null() {
+                        payload["password"] = json!("registry-copy");
+                    }
+                }
+                output.lock().unwrap().write(&ok(payload))
+            }
+            // One connection from before the vault, until it is taken over
+            "get_pref_passwords" => {
+                let legacy = json!({
+                    "host": "legacy", "user": "root", "port": "5432", "name": "old",
+                    "dialect": "POSTGRESQL", "password": "legacy-copy",
+                });
+                let stored = if forgotten.contains(&identity(&legacy)) {
+                    json!([])
+                } else {
+                    json!([legacy])
+                };
+                output.lock().unwrap().write(&ok(stored))
+            }
+            "set_pref_forget_password" => {
+                forgotten.insert(identity(&body));
+                output.lock().unwrap().write(&ok(json!("forgotten")))
+            }
+            "forgotten" => {
+                let mut forgotten: Vec<_> = forgotten.iter().collect();
+                forgotten.sort();
+                output.lock().unwrap().write(&ok(json!(forgotten)))
+            }
+            // Also a kind windows may send, for the policy and routing tests
+            "delay" | "run_sql_query" => {
+                let output = output.clone();
+                let reply = ok(body.clone());
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(body["ms"].as_u64().unwrap_or(0)));
+                    output.lock().unwrap().write(&reply);
+                });
+            }
+            "garbage" => {
+                let output = output.lock().unwrap();
+                let mut stdout = io::stdout().lock();
+                if output.framed {
+                    write!(stdout, "#16\nthis is not json\n").unwrap();
+                } else {
+                    writeln!(stdout, "this is not json").unwrap();
+                }
+                drop(stdout);
+                output.write(&json!({ "status": "ok", "payload": null }));
+                output.write(&json!({ "id": "nobody", "status": "ok", "payload": null }));
+                output.write(&ok(json!("after garbage")));
+            }
+            "big" => {
+                let len = body["len"].as_u64().unwrap_or(0) as usize;
+                output.lock().unwrap().write(&ok(json!("x".repeat(len))));
+            }
+            "stderr" => {
+                eprintln!("{}", body["line"].as_str().unwrap_or_default());
+                output.lock().unwrap().write(&ok(Value::Null))
+            }
+            "crash" => {
+                eprintln!("mock sidecar crashing on purpose");
+                process::exit(3);
+            }
+            // Generates forever, until cancelled
+            "hang" | "get_gen_packets" => {}
+            "fail" => output
+                .lock()
+                .unwrap()
+                .write(&err(body["message"].as_str().unwrap_or("failed"))),
+            kind => output
+                .lock()
+                .unwrap()
+                .write(&err(&format!("Unknown command: {kind}"))),
+        }
+    }
+}
+
+/// `user@host:port/name`, as the vault keys passwords.
+fn identity(creds: &Value) -> String {
+    let port = match &creds["port"] {
+        Value::String(port) => port.clone(),
+        port => port.to_string(),
+    };
+    format!(
+        "{}@{}:{}/{}",
+        creds["user"].as_str().unwrap_or_default(),
+        creds["host"].as_str().unwrap_or_default(),
+        port,
+        creds["name"].as_str().unwrap_or_default()
+    )
+}
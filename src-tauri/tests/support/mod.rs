@@ -0,0 +1,161 @@
+//! The plumbing to run a bridge against the stand-in populator in
+//! `mock_sidecar.rs`.
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use app_lib::bridge::{
+    BridgeBuilder, BridgeError, BridgeState, EventSink, RequestOptions, Shared, Sidecar,
+};
+use app_lib::logging;
+use serde_json::Value;
+
+const WAIT: Duration = Duration::from_secs(10);
+
+/// Installs the process-wide logger on first use, writing to a directory
+/// that outlives every [`Mock`], and holds it so tests that change it take
+/// turns.
+pub fn logger() -> MutexGuard<'static, ()> {
+    static LOGGER: Mutex<()> = Mutex::new(());
+    static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+    let guard = LOGGER.lock().unwrap_or_else(PoisonError::into_inner);
+    LOG_DIR.get_or_init(|| {
+        let dir = env::temp_dir().join(format!("datasmith-bridge-test-logs-{}", process::id()));
+        logging::init(&dir).expect("no other logger is installed");
+        dir
+    });
+    guard
+}
+
+/// Keeps every event the bridge raises, with the window it was meant for.
+#[derive(Default)]
+pub struct Recorder {
+    events: Mutex<Vec<(Option<String>, String, Value)>>,
+}
+
+impl EventSink for Recorder {
+    fn emit_json(&self, event: &str, payload: Value) {
+        self.events
+            .lock()
+            .unwrap()
+            .push((None, event.to_string(), payload));
+    }
+
+    fn emit_json_to(&self, window: &str, event: &str, payload: Value) {
+        self.events
+            .lock()
+            .unwrap()
+            .push((Some(window.to_string()), event.to_string(), payload));
+    }
+}
+
+impl Recorder {
+    pub fn named(&self, event: &str) -> Vec<Value> {
+        self.targeted(event)
+            .into_iter()
+            .map(|(_, payload)| payload)
+            .collect()
+    }
+
+    pub fn targeted(&self, event: &str) -> Vec<(Option<String>, Value)> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, name, _)| name == event)
+            .map(|(window, _, payload)| (window.clone(), payload.clone()))
+            .collect()
+    }
+}
+
+/// A bridge supervising the stand-in, torn down on drop.
+pub struct Mock {
+    pub bridge: Shared,
+    pub events: Arc<Recorder>,
+    supervisor: Option<JoinHandle<()>>,
+    dir: PathBuf,
+}
+
+impl Mock {
+    pub fn start(mode: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "datasmith-bridge-test-{}-{}",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        let events = Arc::new(Recorder::default());
+        let sidecar = Sidecar::new(env!("CARGO_BIN_EXE_mock-sidecar"))
+            .arg(mode)
+            .arg(dir.join("populator"));
+        let (bridge, supervisor) = BridgeBuilder::new(sidecar, dir.clone())
+            .events(events.clone())
+            .spawn();
+        Mock {
+            bridge,
+            events,
+            supervisor: Some(supervisor),
+            dir,
+        }
+    }
+
+    /// Starts the stand-in and waits for its handshake.
+    pub fn ready(mode: &str) -> Self {
+        let mock = Mock::start(mode);
+        mock.wait_for(BridgeState::Ready, 0);
+        mock
+    }
+
+    /// A path in the bridge's own scratch directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn request(&self, kind: &str, body: Value) -> Result<Value, BridgeError> {
+        self.request_with(kind, body, RequestOptions::default())
+    }
+
+    pub fn request_with(
+        &self,
+        kind: &str,
+        body: Value,
+        options: RequestOptions,
+    ) -> Result<Value, BridgeError> {
+        tauri::async_runtime::block_on(self.bridge.request(kind, body, options))
+    }
+
+    pub fn wait_for(&self, state: BridgeState, restarts: u32) {
+        let deadline = Instant::now() + WAIT;
+        loop {
+            let status = self.bridge.status();
+            if status.state == state && status.restarts == restarts {
+                return;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "bridge never reached {state:?} after {restarts} restarts, last {status:?}"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        self.bridge.shutdown(Duration::from_secs(2));
+        if let Some(supervisor) = self.supervisor.take() {
+            supervisor.join().ok();
+        }
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
@@ -0,0 +1,136 @@
+import { invoke } from "@tauri-apps/api/core"
+import { listen } from "@tauri-apps/api/event"
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow"
+
+import type { CredentialSource } from "@/bindings/CredentialSource"
+import type { DbCreds } from "@/bindings/DbCreds"
+import {
+  AuditEntry,
+  AuditQuery,
+  BridgeDiagnostic,
+  BridgeStatus,
+  Handshake,
+  LogEntry,
+  LogFilter,
+  LogLevel,
+  QueueStats,
+  RequestTimeouts,
+  ShellLogQuery,
+  ShellLogRecord,
+  VaultStatus,
+} from "@/components/types"
+
+export function invokeBridgeStatus() {
+  return invoke<BridgeStatus>("bridge_status")
+}
+
+export function invokeBridgeHandshake() {
+  return invoke<Handshake | null>("bridge_handshake")
+}
+
+export function invokeBridgeQueueStats() {
+  return invoke<QueueStats>("bridge_queue_stats")
+}
+
+export function onBridgeStatus(handler: (status: BridgeStatus) => void) {
+  return listen<BridgeStatus>("bridge-status", (event) => handler(event.payload))
+}
+
+export function onBridgeDiagnostic(
+  handler: (diagnostic: BridgeDiagnostic) => void
+) {
+  return getCurrentWebviewWindow().listen<BridgeDiagnostic>(
+    "bridge-diagnostic",
+    (event) => handler(event.payload)
+  )
+}
+
+export function invokeBridgeStderrTail(lines: number = 200) {
+  return invoke<string[]>("bridge_stderr_tail", { lines })
+}
+
+export function invokeCancelRequest(id: string) {
+  return invoke<boolean>("cancel_request", { id })
+}
+
+export function invokeGetRequestTimeouts() {
+  return invoke<RequestTimeouts>("get_request_timeouts")
+}
+
+export function invokeSetRequestTimeouts(timeouts: RequestTimeouts) {
+  return invoke<void>("set_request_timeouts", { timeouts })
+}
+
+export function invokeBridgeJournalStart() {
+  return invoke<string>("bridge_journal_start")
+}
+
+export function invokeBridgeJournalStop() {
+  return invoke<string | null>("bridge_journal_stop")
+}
+
+export function invokeBridgeJournalPath() {
+  return invoke<string | null>("bridge_journal_path")
+}
+
+export function invokeBridgeSocketPath() {
+  return invoke<string | null>("bridge_socket_path")
+}
+
+export function invokeBridgeHttpUrl() {
+  return invoke<string | null>("bridge_http_url")
+}
+
+export function invokeAuditQuery(query: AuditQuery = {}) {
+  return invoke<AuditEntry[]>("audit_query", { query })
+}
+
+export function invokeVaultStatus() {
+  return invoke<VaultStatus>("vault_status")
+}
+
+// Creates the vault under this passphrase when there is none yet
+export function invokeVaultUnlock(passphrase: string, lockAfterSecs?: number) {
+  return invoke<VaultStatus>("vault_unlock", { passphrase, lockAfterSecs })
+}
+
+export function invokeVaultLock() {
+  return invoke<VaultStatus>("vault_lock")
+}
+
+// Asks the user in a native dialog before saving the source
+export function invokeSetCredentialSource(creds: DbCreds, source: CredentialSource) {
+  return invoke<void>("set_credential_source", { creds, source })
+}
+
+// Keyed by `user@host:port/name`
+export function invokeCredentialSources() {
+  return invoke<Record<string, CredentialSource>>("credential_sources")
+}
+
+export function invokeGetLogLevel() {
+  return invoke<LogLevel>("get_log_level")
+}
+
+export function invokeSetLogLevel(level: LogLevel) {
+  return invoke<LogLevel>("set_log_level", { level })
+}
+
+export function invokeLogsQuery(query: ShellLogQuery = {}) {
+  return invoke<ShellLogRecord[]>("logs_query", { query })
+}
+
+// Returns the entries already logged; later ones arrive through onLogEntries
+export function invokeLogsFollow(filter: LogFilter = {}) {
+  return invoke<LogEntry[]>("logs_follow", { filter })
+}
+
+export function invokeLogsUnfollow() {
+  return invoke<void>("logs_unfollow")
+}
+
+export function onLogEntries(handler: (entries: LogEntry[]) => void) {
+  return getCurrentWebviewWindow().listen<LogEntry[]>("log-entries", (event) =>
+    handler(event.payload)
+  )
+}
@@ -1,34 +1,44 @@
 import { invoke } from "@tauri-apps/api/core"
-import { once } from "@tauri-apps/api/event"
 import camelcaseKeys from "camelcase-keys"
 
-import { CliRequest, CliResponse } from "@/components/types"
+import { BridgeErrorKind, CliRequest, RequestOptions } from "@/components/types"
+
+export class BridgeError extends Error {
+  kind: BridgeErrorKind
+
+  constructor(kind: BridgeErrorKind, message: string) {
+    super(message)
+    this.kind = kind
+  }
+}
 
 export async function invokeCliRequest<T = unknown, R = unknown>(
-  req: CliRequest<T>
+  req: CliRequest<T>,
+  options?: RequestOptions
 ): Promise<R> {
-  const id = Date.now().toString() + Math.random().toString(36).slice(2)
-  const taggedReq = { ...req, id }
-  // console.log("Invoking CLI request:", taggedReq)
-  return new Promise(async (resolve, reject) => {
-    const unListen = await once<string>(`py-response-${id}`, (event) => {
-      try {
-        const res = JSON.parse(event.payload || "{}") as CliResponse<R>
-        if (res.status === "ok") {
-          resolve(camelcaseKeys(res.payload || {}, { deep: true }) as R)
-        } else {
-          reject(new Error(res.error || JSON.stringify(res)))
-        }
-      } catch (error: any) {
-        reject(new Error(error?.message || error))
-      } finally {
-        unListen()
-      }
+  try {
+    const payload = await invoke<R>("request", {
+      kind: req.kind,
+      body: req.body ?? null,
+      options: options ?? null,
     })
+    return camelcaseKeys((payload || {}) as Record<string, unknown>, {
+      deep: true,
+    }) as R
+  } catch (error: any) {
+    if (error?.kind) throw new BridgeError(error.kind, error.message)
+    throw new Error(typeof error === "string" ? error : error?.message || error)
+  }
+}
 
-    invoke("send", { payload: JSON.stringify(taggedReq) }).catch((e) => {
-      unListen()
-      reject(e)
-    })
-  })
+export async function invokeCommand<R>(
+  command: string,
+  args?: Record<string, unknown>
+): Promise<R> {
+  try {
+    return await invoke<R>(command, args)
+  } catch (error: any) {
+    if (error?.kind) throw new BridgeError(error.kind, error.message)
+    throw new Error(typeof error === "string" ? error : error?.message || error)
+  }
 }
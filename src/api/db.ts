@@ -6,96 +6,72 @@ import {
   UsageInfo,
 } from "@/components/types"
 
-import { invokeCliRequest } from "./cli"
+import { invokeCommand } from "./cli"
 
 export function invokeDbInfo() {
-  return invokeCliRequest<void, DBCreds>({ kind: "get_db_info" })
+  return invokeCommand<DBCreds>("get_db_info")
 }
 
 export function invokeGetLastConnected() {
-  return invokeCliRequest<void, DBCreds | null>({
-    kind: "get_db_last_connected",
-  })
+  return invokeCommand<DBCreds | null>("get_db_last_connected")
 }
 
 export function invokeDbConnection(dbCreds: DBCreds) {
-  return invokeCliRequest<DBCreds, DBCreds>({
-    kind: "set_db_connect",
-    body: dbCreds,
-  })
+  return invokeCommand<DBCreds>("set_db_connect", { creds: dbCreds })
 }
 
 export function invokeDbDisconnect() {
-  return invokeCliRequest<void, string>({ kind: "set_db_disconnect" })
+  return invokeCommand<string>("set_db_disconnect")
 }
 
 export function invokeListDbCreds() {
-  return invokeCliRequest<void, DBCreds[]>({ kind: "get_pref_connections" })
+  return invokeCommand<DBCreds[]>("get_pref_connections")
 }
 
 export function invokeDbDeletion(dbCreds: DBCreds) {
-  return invokeCliRequest<DBCreds, string>({
-    kind: "set_pref_delete",
-    body: dbCreds,
-  })
+  return invokeCommand<string>("set_pref_delete", { creds: dbCreds })
 }
 
 export function invokeDbReconnection(dbCreds: DBCreds) {
-  return invokeCliRequest<DBCreds, DBCreds>({
-    kind: "set_db_reconnect",
-    body: dbCreds,
-  })
+  return invokeCommand<DBCreds>("set_db_reconnect", { creds: dbCreds })
 }
 
 export function invokeGetTables() {
-  return invokeCliRequest<void, TableEntry[]>({ kind: "get_db_tables" })
+  return invokeCommand<TableEntry[]>("get_db_tables")
 }
 
 export function invokeTableData(table: string) {
-  return invokeCliRequest<{ name: string }, TableMetadata>({
-    kind: "get_db_table",
-    body: { name: table },
-  })
+  return invokeCommand<TableMetadata>("get_db_table", { name: table })
 }
 
 export function invokeGetLogs(lines: number = 200) {
-  return invokeCliRequest<{ lines: number }, string[]>({
-    kind: "get_logs_read",
-    body: { lines },
-  })
+  return invokeCommand<string[]>("get_logs_read", { lines })
 }
 
 export function invokeClearLogs() {
-  return invokeCliRequest<void, []>({ kind: "set_logs_clear" })
+  return invokeCommand<string[]>("set_logs_clear")
 }
 
 export function invokeRunSql(sql: string) {
-  return invokeCliRequest<{ sql: string }, string[]>({
-    kind: "run_sql_query",
-    body: { sql: sql },
-  })
+  return invokeCommand<string[]>("run_sql_query", { sql })
 }
 
 export function invokeGetSqlBanner() {
-  return invokeCliRequest<void, SqlLog>({
-    kind: "get_sql_banner",
-  })
+  return invokeCommand<SqlLog>("get_sql_banner")
 }
 
 export function invokeDbCommit() {
-  return invokeCliRequest<void, string>({ kind: "set_db_commit" })
+  return invokeCommand<string>("set_db_commit")
 }
 
 export function invokeDbRollback() {
-  return invokeCliRequest<void, string>({ kind: "set_db_rollback" })
+  return invokeCommand<string>("set_db_rollback")
 }
 
 export function invokeDbGetUncommitted() {
-  return invokeCliRequest<void, number>({ kind: "get_uncommitted_db" })
+  return invokeCommand<number>("get_uncommitted_db")
 }
 
 export function invokeGetRowsConfig() {
-  return invokeCliRequest<void, UsageInfo[]>({
-    kind: "get_pref_rows",
-  })
+  return invokeCommand<UsageInfo[]>("get_pref_rows")
 }
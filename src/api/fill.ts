@@ -1,63 +1,60 @@
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow"
+
+import { GenDone } from "@/bindings/GenDone"
+import { GenProgressEvent } from "@/bindings/GenProgressEvent"
+import { PendingWrites } from "@/bindings/PendingWrites"
 import {
-  PacketProgress,
   TablePacket,
   TablePacketRequest,
   TableSpec,
 } from "@/components/types"
 
-import { invokeCliRequest } from "./cli"
+import { invokeCommand } from "./cli"
 
 export function invokeGetFakerMethods() {
-  return invokeCliRequest<void, string[]>({ kind: "get_gen_methods" })
+  return invokeCommand<string[]>("get_gen_methods")
 }
 
 export function invokeGenPackets(tableSpec: TableSpec) {
-  return invokeCliRequest<TableSpec, PacketProgress>({
-    kind: "get_gen_packets",
-    body: tableSpec,
+  return invokeCommand<TablePacketRequest>("get_gen_packets", {
+    spec: tableSpec,
   })
 }
 
 export function invokeGetGenPacket(packetId: string, page: number) {
-  return invokeCliRequest<{ packetId: string; page: number }, TablePacket>({
-    kind: "get_gen_packet",
-    body: { packetId: packetId, page: page },
-  })
+  return invokeCommand<TablePacket>("get_gen_packet", { packetId, page })
 }
 
 export function invokeLoadSpec(dbId: number, tableName: string) {
-  return invokeCliRequest<Record<string, string | number>, TableSpec>({
-    kind: "get_pref_spec",
-    body: {
-      dbId: dbId,
-      tableName: tableName,
-    },
-  })
+  return invokeCommand<TableSpec | null>("get_pref_spec", { dbId, tableName })
 }
 
 export function invokeInsertSqlPacket(packetId: string) {
-  return invokeCliRequest<{ packetId: string }, { pendingWrites: number }>({
-    kind: "set_db_insert",
-    body: { packetId: packetId },
-  })
+  return invokeCommand<PendingWrites>("set_db_insert", { packetId })
 }
 
 export function invokeExportSqlPacket(packetId: string, path: string) {
-  return invokeCliRequest<{ path: string; packetId: string }, string>({
-    kind: "set_db_export",
-    body: { packetId: packetId, path: path },
-  })
+  return invokeCommand<string>("set_db_export", { packetId, path })
 }
 
 export function invokeClearGenPackets() {
-  return invokeCliRequest<void, string>({
-    kind: "clear_gen_packets",
-  })
+  return invokeCommand<string>("clear_gen_packets")
 }
 
 export function invokeGetGenResult(jobId: string) {
-  return invokeCliRequest<{ jobId: string }, TablePacketRequest>({
-    kind: "poll_gen_status",
-    body: { jobId: jobId },
-  })
+  return invokeCommand<TablePacketRequest>("poll_gen_status", { jobId })
+}
+
+// Targeted at the window that started the job, so other windows never see it
+export function onGenProgress(handler: (progress: GenProgressEvent) => void) {
+  return getCurrentWebviewWindow().listen<GenProgressEvent>(
+    "gen-progress",
+    (event) => handler(event.payload)
+  )
+}
+
+export function onGenDone(handler: (done: GenDone) => void) {
+  return getCurrentWebviewWindow().listen<GenDone>("gen-done", (event) =>
+    handler(event.payload)
+  )
 }
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ForeignKeyRef } from "./ForeignKeyRef";
+
+export type ColumnMetadata = { name: string, type: string, unique: boolean, multiUnique: Array<string> | null, primaryKey: boolean, nullable: boolean, default: string | null, autoincrement: boolean, computed: boolean, foreignKeys: ForeignKeyRef | null, length: number | null, precision: number | null, scale: number | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CredentialSource = { "type": "env", name: string, } | { "type": "dotEnv", path: string, key: string, } | { "type": "command", command: string, };
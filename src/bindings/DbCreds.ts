@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CredentialSource } from "./CredentialSource";
+import type { DbDialect } from "./DbDialect";
+
+export type DbCreds = { id?: number, host: string, user: string, port: string, name: string, dialect: DbDialect, password?: string, 
+/**
+ * Where to read the password instead; the shell resolves it, so the
+ * populator never sees this.
+ */
+passwordSource?: CredentialSource, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TablePacket } from "./TablePacket";
+
+/**
+ * Payload of the `gen-done` event; exactly one of `data` and `error` is set.
+ */
+export type GenDone = { jobId: string, data: TablePacket | null, error: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of the `gen-progress` event, throttled by the bridge.
+ */
+export type GenProgressEvent = { jobId: string, status: string, row: number, total: number, column: string | null, rowsPerSec: number | null, etaMs: number | null, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GenProgress } from "./GenProgress";
+import type { GenState } from "./GenState";
+import type { TablePacket } from "./TablePacket";
+
+/**
+ * Reply to `get_gen_packets` and `poll_gen_status`.
+ */
+export type GenStatus = { status: GenState, message: string, jobId: string, data: TablePacket | null, progress: GenProgress, };
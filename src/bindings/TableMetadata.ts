@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ColumnMetadata } from "./ColumnMetadata";
+
+export type TableMetadata = { name: string, parents: Array<string>, columns: Array<ColumnMetadata>, };
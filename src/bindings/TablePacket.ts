@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ErrorPacket } from "./ErrorPacket";
+
+export type TablePacket = { id: string, name: string, columns: Array<string> | null, entries: Array<Array<string | null>>, errors: Array<ErrorPacket> | null, page: number, pageSize: number, totalPages: number, totalEntries: number, };
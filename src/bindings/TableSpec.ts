@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ColumnSpec } from "./ColumnSpec";
+
+export type TableSpec = { dbId?: number, pageSize: number, name: string, noOfEntries: number, columns: Array<ColumnSpec>, };
@@ -0,0 +1,35 @@
+import { useEffect, useState } from "react"
+import { invokeBridgeStatus, onBridgeStatus } from "@/api/bridge"
+import { Icon } from "@iconify/react"
+
+import { BridgeStatus } from "@/components/types"
+
+export function BridgeGate({ children }: { children: React.ReactNode }) {
+  const [status, setStatus] = useState<BridgeStatus | null>(null)
+
+  useEffect(() => {
+    invokeBridgeStatus().then(setStatus).catch(console.error)
+    const unListen = onBridgeStatus(setStatus)
+    return () => {
+      unListen.then((fn) => fn())
+    }
+  }, [])
+
+  if (status?.state !== "incompatible") return <>{children}</>
+
+  return (
+    <div className="flex h-full flex-col items-center justify-center space-y-4 p-8 text-center">
+      <Icon icon="mdi:connection" className="h-12 w-12 text-red-500" />
+      <h2 className="text-lg font-semibold">Incompatible backend</h2>
+      <p className="max-w-xl text-sm text-muted-foreground">
+        The bundled populator cannot be used with this version of DataSmith.
+        Please reinstall or update the application.
+      </p>
+      {status.message && (
+        <pre className="max-w-xl whitespace-pre-wrap rounded bg-muted p-4 text-left text-xs">
+          {status.message}
+        </pre>
+      )}
+    </div>
+  )
+}
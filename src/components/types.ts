@@ -1,3 +1,28 @@
+import type { ColumnMetadata } from "@/bindings/ColumnMetadata"
+import type { ColumnSpec } from "@/bindings/ColumnSpec"
+import type { DbCreds } from "@/bindings/DbCreds"
+import type { DbDialect } from "@/bindings/DbDialect"
+import type { ErrorPacket } from "@/bindings/ErrorPacket"
+import type { GenStatus } from "@/bindings/GenStatus"
+import type { GeneratorType } from "@/bindings/GeneratorType"
+import type { SqlBanner } from "@/bindings/SqlBanner"
+import type { TableEntry } from "@/bindings/TableEntry"
+import type { TableMetadata } from "@/bindings/TableMetadata"
+import type { TablePacket } from "@/bindings/TablePacket"
+import type { TableSpec } from "@/bindings/TableSpec"
+import type { UsageInfo } from "@/bindings/UsageInfo"
+
+export type {
+  ColumnSpec,
+  ErrorPacket,
+  GeneratorType,
+  TableEntry,
+  TableMetadata,
+  TablePacket,
+  TableSpec,
+  UsageInfo,
+}
+
 export type CliResponse<T> = Record<string, unknown> & {
   status: "ok" | "error"
   payload?: T
@@ -10,53 +35,11 @@ export type CliRequest<T> = Record<string, unknown> & {
   body?: T
 }
 
-export type DBDialectType =
-  | "MYSQL"
-  | "UNKNOWN"
-  | "POSTGRESQL"
-  | "MSSQL"
-  | "ORACLE"
-  | "MARIADB"
-  | "FIREBIRD"
+export type DBDialectType = DbDialect
 
-export interface DBCreds {
-  id?: number
-  host: string
-  user: string
-  port: string
-  name: string
-  dialect: DBDialectType
-  password?: string
-  error?: string
-}
+export type DBCreds = DbCreds & { error?: string }
 
-export interface ColumnData {
-  name: string
-  type: string
-  unique: boolean
-  multiUnique: string[] | null
-  primaryKey: boolean
-  nullable: boolean
-  default: string | null
-  autoincrement: boolean
-  computed: boolean
-  foreignKeys: { table: string; column: string }
-  length: number | null
-  precision: number | null
-  scale: number | null
-}
-
-export interface TableMetadata {
-  name: string
-  parents: string[]
-  columns: ColumnData[]
-}
-
-export interface TableEntry {
-  name: string
-  parents: number
-  rows: number
-}
+export type ColumnData = ColumnMetadata
 
 export interface DataEntry {}
 
@@ -67,28 +50,6 @@ export interface DataPackage {
   inserted: boolean
 }
 
-export type GeneratorType =
-  | "faker"
-  | "regex"
-  | "foreign"
-  | "autoincrement"
-  | "computed"
-  | "python"
-  | "constant"
-  | "null"
-
-export interface TableSpec {
-  name: string
-  pageSize: number
-  noOfEntries: number
-  columns: ColumnSpec[]
-}
-export interface ColumnSpec {
-  name: string
-  generator: string | null
-  type: GeneratorType | null
-}
-
 export type ColumnSpecMap = Record<string, ColumnSpec>
 export interface TableSpecEntry {
   name: string
@@ -98,12 +59,6 @@ export interface TableSpecEntry {
 
 export type TableSpecMap = Record<string, TableSpecEntry>
 
-export interface ErrorPacket {
-  msg: string | null
-  column: string | null
-  type: "warning" | "error"
-}
-
 export type ErrorPacketMap = Record<string, ErrorPacket[]>
 
 export interface PacketProgress {
@@ -115,34 +70,131 @@ export interface PacketProgress {
   eta: string | null
 }
 
-export interface TablePacketRequest {
-  status: "done" | "pending"
-  message: string
-  jobId: string
-  data: TablePacket | null
-  progress: PacketProgress
+export type TablePacketRequest = GenStatus
+
+export type SqlLog = SqlBanner
+
+export type BridgeState =
+  | "starting"
+  | "ready"
+  | "crashed"
+  | "restarting"
+  | "incompatible"
+
+export interface BridgeStatus {
+  state: BridgeState
+  restarts: number
+  code: number | null
+  message: string | null
 }
 
-export interface TablePacket {
-  id: string
+export type BridgeErrorKind =
+  | "missing"
+  | "queue-full"
+  | "io"
+  | "timeout"
+  | "cancelled"
+  | "exited"
+  | "sidecar"
+  | "protocol"
+  | "rejected"
+
+export interface RequestOptions {
+  id?: string
+  timeoutMs?: number
+}
+
+export interface RequestTimeouts {
+  defaultMs: number
+  kinds: Record<string, number>
+}
+
+export interface BridgeDiagnostic {
+  kind: "non-json" | "missing-id" | "unknown-id"
+  id: string | null
+  raw: string
+}
+
+export interface QueueStats {
+  depth: number
+  capacity: number
+}
+
+export interface Handshake {
+  protocol: number
+  version: string
+  kinds: string[]
+  framing: "line" | "length"
+  logDir?: string | null
+}
+
+export interface AuditConnection {
+  id: number | null
   name: string
-  columns: string[] | null
-  entries: (string | null)[][]
-  errors: ErrorPacket[] | null
+  user: string
+  host: string
+  port: string
+  dialect: DBDialectType
+}
+
+export interface AuditEntry {
+  at: number
+  requestId: string | null
+  kind: string
+  window: string | null
+  connection: AuditConnection | null
+  table: string | null
+  packetId: string | null
+  pendingWrites: number | null
+  sql: string | null
+  path: string | null
+  outcome: "ok" | "error" | "abandoned"
+  error: string | null
+  late: boolean
+}
+
+export interface AuditQuery {
+  since?: string
+  until?: string
+  connection?: string
+  limit?: number
+}
 
-  page: number
-  pageSize: number
-  totalPages: number
-  totalEntries: number
+export interface VaultStatus {
+  exists: boolean
+  unlocked: boolean
+  lockAfterSecs: number | null
 }
 
-export interface SqlLog {
-  log: string[]
-  prompt: string
+export type LogLevel = "off" | "error" | "warn" | "info" | "debug" | "trace"
+
+export interface ShellLogRecord {
+  at: number
+  level: "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE"
+  target: string
+  message: string
+}
+
+export interface ShellLogQuery {
+  level?: LogLevel
+  target?: string
+  contains?: string
+  since?: number
+  limit?: number
+}
+
+export type LogSource = "shell" | "populator" | "stderr"
+
+export interface LogEntry {
+  seq: number
+  at: number
+  source: LogSource
+  level: ShellLogRecord["level"]
+  target: string
+  message: string
 }
 
-export interface UsageInfo {
-  tableName: string
-  totalRows: number
-  newRows: number
+export interface LogFilter {
+  level?: LogLevel
+  sources?: LogSource[]
 }
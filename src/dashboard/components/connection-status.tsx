@@ -1,4 +1,5 @@
 import { useEffect, useState } from "react"
+import { onBridgeStatus } from "@/api/bridge"
 import {
   invokeDbConnection,
   invokeDbDeletion,
@@ -42,6 +43,7 @@ import {
 } from "@/components/ui/dropdown-menu"
 import { Input } from "@/components/ui/input"
 import { Label } from "@/components/ui/label"
+import { toast } from "@/components/ui/use-toast"
 import {
   Popover,
   PopoverContent,
@@ -75,6 +77,25 @@ export default function ConnectionSelector({
     })()
   }, [])
 
+  useEffect(() => {
+    const unListen = onBridgeStatus(async (status) => {
+      if (status.state === "crashed") {
+        toast({
+          variant: "destructive",
+          title: "Backend crashed",
+          description: `${status.message ?? "The populator stopped unexpectedly."} Restarting...`,
+        })
+      } else if (status.state === "ready" && status.restarts > 0) {
+        updateDb(null)
+        await handleListDbCreds()
+        await handleLastConnected()
+      }
+    })
+    return () => {
+      unListen.then((fn) => fn())
+    }
+  }, [])
+
   async function handleLastConnected() {
     try {
       const lastConnected = await invokeGetLastConnected()
@@ -367,7 +367,7 @@ function RenderGenerator({
     <Popover open={false}>
       <PopoverTrigger asChild>
         <span
-          title={`Table: ${column.foreignKeys.table} Column: ${column.foreignKeys.column}`}
+          title={`Table: ${column.foreignKeys?.table} Column: ${column.foreignKeys?.column}`}
         >
           <Button
             variant="outline"
@@ -377,7 +377,7 @@ function RenderGenerator({
             disabled
           >
             <Icon icon="tabler:table-filled" className="mr-2 h-4 w-4" />
-            {column.foreignKeys.table}__{column.foreignKeys.column}
+            {column.foreignKeys?.table}__{column.foreignKeys?.column}
           </Button>
         </span>
       </PopoverTrigger>
@@ -1,36 +1,86 @@
 import { startTransition, useEffect, useState } from "react"
-import { invokeClearLogs, invokeGetLogs } from "@/api/db"
+import {
+  invokeBridgeJournalPath,
+  invokeBridgeJournalStart,
+  invokeBridgeJournalStop,
+  invokeLogsFollow,
+  invokeLogsUnfollow,
+  onBridgeDiagnostic,
+  onLogEntries,
+} from "@/api/bridge"
+import { invokeClearLogs } from "@/api/db"
 import { Icon } from "@iconify/react"
 
 import { cn } from "@/lib/utils"
+import { LogEntry, LogLevel, LogSource } from "@/components/types"
 import { toast } from "@/components/ui/use-toast"
 
+const MAX_ENTRIES = 500
+const SOURCES: LogSource[] = ["populator", "shell", "stderr"]
+const LEVELS: LogLevel[] = ["error", "warn", "info", "debug"]
+
 export default function RenderLogs({ activeTab }: { activeTab?: string }) {
-  const [logs, setLogs] = useState<string[]>([])
-  const [showCheck, setShowCheck] = useState<boolean>(false)
+  const [entries, setEntries] = useState<LogEntry[]>([])
+  const [level, setLevel] = useState<LogLevel>("info")
+  const [sources, setSources] = useState<LogSource[]>(SOURCES)
+  const [diagnostics, setDiagnostics] = useState<string[]>([])
+  const [journalPath, setJournalPath] = useState<string | null>(null)
 
   useEffect(() => {
-    if (activeTab === "log") {
-      retrieveLogs()
-      const intervalID = setInterval(() => {
-        retrieveLogs()
-      }, 1000)
+    invokeBridgeJournalPath().then(setJournalPath)
+  }, [])
 
-      return () => clearInterval(intervalID)
+  useEffect(() => {
+    const unListen = onBridgeDiagnostic((diagnostic) => {
+      setDiagnostics((prev) =>
+        [...prev, `[${diagnostic.kind}] ${diagnostic.raw}`].slice(-10)
+      )
+    })
+    return () => {
+      unListen.then((fn) => fn())
     }
-  }, [activeTab])
+  }, [])
+  const [showCheck, setShowCheck] = useState<boolean>(false)
 
-  function retrieveLogs() {
-    invokeGetLogs(10)
-      .then((logs) => {
-        startTransition(() => {
-          setLogs(logs)
-        })
+  useEffect(() => {
+    if (activeTab !== "log") return
+
+    setEntries([])
+    const unListen = onLogEntries((batch) => {
+      startTransition(() => {
+        setEntries((prev) => [...prev, ...batch].slice(-MAX_ENTRIES))
+      })
+    })
+    invokeLogsFollow({ level, sources })
+      .then((missed) => {
+        // Batches that arrived before the reply are newer than all of these
+        const last = missed.at(-1)?.seq ?? 0
+        setEntries((prev) =>
+          [...missed, ...prev.filter((entry) => entry.seq > last)].slice(
+            -MAX_ENTRIES
+          )
+        )
       })
       .catch((error) => {
-        console.error("Error fetching logs:", error)
-        setLogs([])
+        console.error("Error following logs:", error)
+        setEntries([])
       })
+
+    return () => {
+      unListen.then((fn) => fn())
+      invokeLogsUnfollow()
+    }
+  }, [activeTab, level, sources])
+
+  // No sources would mean every source to the bridge, so one always stays
+  function toggleSource(source: LogSource) {
+    setSources((prev) =>
+      prev.includes(source)
+        ? prev.length > 1
+          ? prev.filter((s) => s !== source)
+          : prev
+        : SOURCES.filter((s) => s === source || prev.includes(s))
+    )
   }
 
   const colorForLog = (log: string): string => {
@@ -42,12 +92,22 @@ export default function RenderLogs({ activeTab }: { activeTab?: string }) {
     return "text-muted-foreground";
   };
 
-  const RenderLogs = ({ logs }: { logs: string[] }) => {
+  const colorForEntry = (entry: LogEntry): string => {
+    if (entry.level === "ERROR" || entry.source === "stderr")
+      return "text-red-400"
+    if (entry.level === "WARN") return "text-yellow-500"
+    return colorForLog(entry.message)
+  }
+
+  const RenderEntries = ({ entries }: { entries: LogEntry[] }) => {
     return (
       <p className="text-sm p-2 rounded overflow-auto font-medium scrollbar-none">
-        {logs.map((log, idx) => (
-          <span key={idx} className={colorForLog(log)}>
-            {log} <br />
+        {entries.map((entry) => (
+          <span key={entry.seq} className={colorForEntry(entry)}>
+            <span className="font-mono text-xs opacity-60">
+              {new Date(entry.at).toLocaleTimeString()} {entry.source}{" "}
+            </span>
+            {entry.message} <br />
           </span>
         ))}
       </p>
@@ -56,8 +116,10 @@ export default function RenderLogs({ activeTab }: { activeTab?: string }) {
 
   function clearLogs() {
     invokeClearLogs()
-      .then((logs) => {
-        setLogs(logs)
+      .then(() => {
+        setEntries((prev) =>
+          prev.filter((entry) => entry.source !== "populator")
+        )
         setShowCheck(true)
         setTimeout(() => {
           setShowCheck(false)
@@ -73,10 +135,62 @@ export default function RenderLogs({ activeTab }: { activeTab?: string }) {
       })
   }
 
+  function toggleJournal() {
+    const toggle = journalPath
+      ? invokeBridgeJournalStop().then((path) => {
+          setJournalPath(null)
+          if (path) {
+            toast({ title: "Traffic recorded", description: path })
+          }
+        })
+      : invokeBridgeJournalStart().then(setJournalPath)
+    toggle.catch((error) => {
+      toast({
+        variant: "destructive",
+        title: "Error recording traffic",
+        description: error.message,
+      })
+    })
+  }
+
   return (
     <div className="flex-1 overflow-auto">
       <div className="m-4 mt-auto">
-        <RenderLogs logs={logs} />
+        <div className="flex items-center gap-2 p-2 text-xs">
+          <select
+            className="rounded border bg-inherit px-2 py-1"
+            value={level}
+            onChange={(e) => setLevel(e.target.value as LogLevel)}
+          >
+            {LEVELS.map((level) => (
+              <option key={level} value={level}>
+                {level}
+              </option>
+            ))}
+          </select>
+          {SOURCES.map((source) => (
+            <button
+              key={source}
+              className={cn(
+                "rounded border px-2 py-1 hover:bg-muted",
+                !sources.includes(source) && "opacity-50"
+              )}
+              onClick={() => toggleSource(source)}
+            >
+              {source}
+            </button>
+          ))}
+        </div>
+        <RenderEntries entries={entries} />
+        {diagnostics.length > 0 && (
+          <p className="rounded p-2 font-mono text-xs text-yellow-500">
+            {diagnostics.map((log, idx) => (
+              <span key={idx}>
+                {log} <br />
+              </span>
+            ))}
+          </p>
+        )}
         {Array.from({ length: 4 }).map((_, index) => (
           <br key={index} />
         ))}
@@ -93,6 +207,18 @@ export default function RenderLogs({ activeTab }: { activeTab?: string }) {
             className="mr-4 h-6 w-6 animate-fade-in-out-once text-green-500"
           />
         )}
+        <button
+          className={cn(
+            "mr-2 flex items-center rounded border px-4 py-2 text-sm font-medium",
+            "hover:bg-muted",
+            journalPath && "text-red-500"
+          )}
+          title={journalPath ?? "Record bridge traffic to a journal file"}
+          onClick={() => toggleJournal()}
+        >
+          <Icon icon="mdi:record-circle-outline" className="mr-2" />
+          {journalPath ? "Stop Recording" : "Record Traffic"}
+        </button>
         <button
           className={cn(
             "flex items-center rounded border px-4 py-2 text-sm font-medium",
@@ -1,11 +1,13 @@
 import { useEffect, useRef, useState } from "react"
+import { invokeCancelRequest } from "@/api/bridge"
 import {
   invokeClearGenPackets,
   invokeExportSqlPacket,
   invokeGenPackets,
   invokeGetGenPacket,
-  invokeGetGenResult,
   invokeInsertSqlPacket,
+  onGenDone,
+  onGenProgress,
 } from "@/api/fill"
 import { Icon } from "@iconify/react"
 import { save } from "@tauri-apps/plugin-dialog"
@@ -28,6 +30,7 @@ import {
   TableRow,
 } from "@/components/ui/table"
 import { toast } from "@/components/ui/use-toast"
+import { GenDone } from "@/bindings/GenDone"
 import {
   ColumnSpec,
   PacketProgress,
@@ -118,93 +121,40 @@ export default function RenderPreview({
     }
   }, [tablePacket?.errors])
 
-  useEffect(() => {
-    if (!pendingJobId) return
-    setPreviewLoading(true)
-
-    const interval = setInterval(() => {
-      invokeGetGenResult(pendingJobId)
-        .then((result) => {
-          if (result.status === "done" && result.data) {
-            setTablePacket(result.data)
-            clearInterval(interval)
-            setPreviewLoading(false)
-            setPendingJobId(null)
-            setProgress(null)
-            clearSamples()
-          } else {
-            setProgress({
-              ...result.progress,
-              eta: calculateEta(
-                result.progress.row,
-                result.progress.total
-              ),
-            })
-          }
-        })
-        .catch((error) => {
-          console.error("Error fetching generation result:", error)
-          setPreviewLoading(false)
-          clearInterval(interval)
-          setPendingJobId(null)
-          clearSamples()
-        })
-    }, 500)
-
-    return () => clearInterval(interval)
-  }, [pendingJobId])
+  // Listeners outlive a single job so a `gen-done` that beats the reply
+  // carrying its job id is still picked up
+  const jobRef = useRef<string | null>(null)
+  const lastDone = useRef<GenDone | null>(null)
 
-  type Sample = {
-    row: number
-    time: number
-  }
-
-  const samples = useRef<Sample[]>([])
-  const worstRate = useRef<number | null>(null)
-
-  function clearSamples() {
-    samples.current = []
-    worstRate.current = null
-  }
-
-  function calculateEta(row: number, total: number): string | null {
-    const now = Date.now()
-    if (!total || row <= 0 || row >= total) {
-      samples.current = [{ row, time: now }]
-      worstRate.current = null
-      return null
+  useEffect(() => {
+    const unlistenProgress = onGenProgress((update) => {
+      if (update.jobId !== jobRef.current) return
+      setProgress({ ...update, eta: formatEta(update.etaMs) })
+    })
+    const unlistenDone = onGenDone((done) => {
+      lastDone.current = done
+      if (done.jobId === jobRef.current) finishGeneration(done)
+    })
+    return () => {
+      unlistenProgress.then((unlisten) => unlisten())
+      unlistenDone.then((unlisten) => unlisten())
     }
+  }, [])
 
-    samples.current.push({ row, time: now })
-    if (samples.current.length > 20) samples.current.shift()
-
-    const rates: number[] = []
-    for (let i = 1; i < samples.current.length; i++) {
-      const deltaR = samples.current[i].row - samples.current[i - 1].row
-      const deltaT = samples.current[i].time - samples.current[i - 1].time
-      if (deltaR > 0 && deltaT > 0) {
-        rates.push(deltaT / deltaR)
-      }
+  function finishGeneration(done: GenDone | null) {
+    if (done?.data) {
+      setTablePacket(done.data)
+    } else if (done?.error) {
+      console.error("Error fetching generation result:", done.error)
     }
+    jobRef.current = null
+    setPreviewLoading(false)
+    setPendingJobId(null)
+    setProgress(null)
+  }
 
-    if (rates.length < 3) return null
-
-    const avg = rates.reduce((a, b) => a + b, 0) / rates.length
-    const slowest = Math.max(...rates)
-    worstRate.current = worstRate.current
-      ? Math.max(worstRate.current, slowest)
-      : slowest
-
-    // Confidence increases as more rows processed
-    const confidence = Math.min(Math.pow(row / total, 1.9), 0.8)
-    const exaggeratedWorst = worstRate.current! * 1.9
-
-    // Start harsh, then blend towards avg
-    const blendedRate =
-      (1 - confidence) * exaggeratedWorst + confidence * avg
-
-    const remaining = total - row
-    const etaMs = blendedRate * remaining
+  function formatEta(etaMs: number | null): string | null {
+    if (etaMs === null) return null
     const seconds = Math.floor(etaMs / 1000)
     const mins = Math.floor(seconds / 60)
     const secs = seconds % 60
@@ -214,8 +164,6 @@ export default function RenderPreview({
       : `${secs} second${secs !== 1 ? "s" : ""} remaining`
   }
 
-
-
   function handleInsertPacket() {
     if (!tablePacket) return
     setLoading(true)
@@ -244,8 +192,12 @@ export default function RenderPreview({
 
   async function handleClearGenPackets() {
     try {
-      clearSamples()
-      invokeClearGenPackets()
+      if (pendingJobId) {
+        invokeCancelRequest(pendingJobId)
+        finishGeneration(null)
+      } else {
+        invokeClearGenPackets()
+      }
     } catch (error) {
       console.error("Error clearing packets", error)
     }
@@ -266,8 +218,13 @@ export default function RenderPreview({
     }
     try {
       const res = await invokeGenPackets(newTableSpec)
+      jobRef.current = res.jobId
       setPendingJobId(res.jobId)
-      setProgress(res)
+      setPreviewLoading(true)
+      setProgress({ ...res.progress, jobId: res.jobId, eta: null })
+      if (lastDone.current?.jobId === res.jobId) {
+        finishGeneration(lastDone.current)
+      }
       setNeedsRefresh(false)
       // console.log("Generation started with job ID:", res.jobId)
     } catch (error) {
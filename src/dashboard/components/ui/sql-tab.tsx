@@ -110,6 +110,8 @@ export default function SqlInsertionTab({
       // console.log("SQL Execution Result:", resLog)
       return resLog
     } catch (error: any) {
+      // Declined in the confirmation dialog
+      if (error.kind === "cancelled") return []
       console.error("Error executing SQL:", error)
       toast({
         variant: "destructive",